@@ -0,0 +1,612 @@
+use std::path::PathBuf;
+
+use futures::StreamExt;
+
+use crate::command::handler::{check_policy, deploy_request, record_history, safe_create_deploy};
+use crate::command::output::{ApplyAction, ApplyResult, SpecDrift};
+use crate::command::types::{ApplyCommand, DeployCommand, DiffCommand, Output};
+use crate::local::{DeploymentAction, DeploymentSpec, ProjectSpec};
+use crate::subquery::{ApiVersion, CreateDeployRequest, Deployment, DeploymentType, Project};
+use crate::{Subquery, SubqueryError};
+
+pub async fn handle_apply(
+  subquery: &Subquery,
+  command: ApplyCommand,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let mut results = vec![];
+  let mut specs = vec![];
+  for path in spec_files(&command)? {
+    match ProjectSpec::load(&path) {
+      Ok(v) => specs.push(v),
+      Err(e) => results.push(ApplyResult {
+        project_key: path.display().to_string(),
+        resource: "spec".to_string(),
+        action: ApplyAction::Failed,
+        message: Some(e.to_string()),
+      }),
+    }
+  }
+  let concurrency = command.concurrency.max(1);
+  let dry_run = command.dry_run;
+
+  // create or update all projects first, the deployments need the project exists
+  let projects = futures::stream::iter(&specs)
+    .map(|spec| async move {
+      let result = apply_project_spec(subquery, spec, dry_run).await;
+      (spec, result)
+    })
+    .buffer_unordered(concurrency)
+    .collect::<Vec<_>>()
+    .await;
+  let mut deployable = vec![];
+  for (spec, result) in projects {
+    let key = spec.project_key();
+    match result {
+      Ok((action, message, exists)) => {
+        results.push(ApplyResult {
+          project_key: key,
+          resource: "project".to_string(),
+          action,
+          message,
+        });
+        deployable.push((spec, exists));
+      }
+      Err(e) => results.push(ApplyResult {
+        project_key: key,
+        resource: "project".to_string(),
+        action: ApplyAction::Failed,
+        message: Some(e.to_string()),
+      }),
+    }
+  }
+
+  let deployments = futures::stream::iter(deployable)
+    .map(|(spec, exists)| {
+      apply_deployment_specs(subquery, spec, exists, dry_run, command.override_policy)
+    })
+    .buffer_unordered(concurrency)
+    .collect::<Vec<Vec<ApplyResult>>>()
+    .await;
+  results.extend(deployments.into_iter().flatten());
+  // stable sort, the project is always before its deployments
+  results.sort_by(|a, b| a.project_key.cmp(&b.project_key));
+
+  let failed = results
+    .iter()
+    .filter(|v| v.action == ApplyAction::Failed)
+    .count();
+  crate::command::output::output_apply(results, output)?;
+  if failed > 0 {
+    return Err(SubqueryError::Custom(format!("Failed to apply {} resources", failed)).into());
+  }
+  Ok(())
+}
+
+/// The spec file, or all yaml and toml files in the spec directory
+fn spec_files(command: &ApplyCommand) -> color_eyre::Result<Vec<PathBuf>> {
+  if let Some(file) = &command.file {
+    return Ok(vec![file.clone()]);
+  }
+  let dir = match &command.dir {
+    Some(v) => v,
+    None => return Err(SubqueryError::Custom("Missing --file or --dir".to_string()).into()),
+  };
+  let mut files = std::fs::read_dir(dir)?
+    .filter_map(|entry| entry.ok().map(|v| v.path()))
+    .filter(|path| {
+      path.is_file()
+        && path
+          .extension()
+          .and_then(|v| v.to_str())
+          .map(|v| ["yaml", "yml", "toml"].contains(&v.to_lowercase().as_str()))
+          .unwrap_or(false)
+    })
+    .collect::<Vec<PathBuf>>();
+  files.sort();
+  Ok(files)
+}
+
+pub async fn handle_spec_diff(
+  subquery: &Subquery,
+  command: DiffCommand,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let spec = ProjectSpec::load(&command.file)?;
+  let drifts = spec_drifts(subquery, &spec).await?;
+  crate::command::output::output_spec_drift(&drifts, output)?;
+  if !drifts.is_empty() {
+    std::process::exit(1);
+  }
+  Ok(())
+}
+
+/// Compare the spec with live state field by field, the fields not set in spec are ignored.
+async fn spec_drifts(
+  subquery: &Subquery,
+  spec: &ProjectSpec,
+) -> color_eyre::Result<Vec<SpecDrift>> {
+  let key = spec.project_key();
+  let project = subquery.project(&key).await?;
+  let mut drifts = project_drifts(spec, project.as_ref());
+  if project.is_none() {
+    return Ok(drifts);
+  }
+
+  let deployments = subquery.deployments(&key).await?;
+  let specs = [
+    (DeploymentType::Stage, spec.stage.as_ref()),
+    (DeploymentType::Primary, spec.primary.as_ref()),
+  ];
+  for (type_, deployment) in specs {
+    let deployment = match deployment {
+      Some(v) => v,
+      None => continue,
+    };
+    let current = match deployments.iter().find(|&item| item.type_ == type_) {
+      Some(current) => {
+        let command = deploy_command(deployment, type_.clone(), false);
+        let desired = deploy_request(&command, Some(current));
+        let desired = safe_create_deploy(subquery, desired, &key, Some(&deployment.branch)).await?;
+        Some((current, desired))
+      }
+      None => None,
+    };
+    drifts.extend(deployment_drifts(
+      &key,
+      &type_,
+      deployment,
+      current
+        .as_ref()
+        .map(|(current, desired)| (*current, desired)),
+    ));
+  }
+  Ok(drifts)
+}
+
+fn drift(key: &str, resource: &str, field: &str, desired: String, live: String) -> SpecDrift {
+  SpecDrift {
+    project_key: key.to_string(),
+    resource: resource.to_string(),
+    field: field.to_string(),
+    desired,
+    live,
+  }
+}
+
+/// The project metadata different from the spec, the project itself if not found
+fn project_drifts(spec: &ProjectSpec, project: Option<&Project>) -> Vec<SpecDrift> {
+  let key = spec.project_key();
+  let project = match project {
+    Some(v) => v,
+    None => {
+      return vec![drift(
+        &key,
+        "project",
+        "Project",
+        key.clone(),
+        "not found".to_string(),
+      )]
+    }
+  };
+  let metadata = &spec.project;
+  let fields = [
+    ("Name", metadata.name.clone(), project.name.clone()),
+    (
+      "Subtitle",
+      metadata.subtitle.clone(),
+      project.subtitle.clone(),
+    ),
+    (
+      "Description",
+      metadata.description.clone(),
+      project.description.clone(),
+    ),
+    (
+      "Hide",
+      metadata.hide.map(|v| v.to_string()),
+      project.hide.map(|v| v.to_string()),
+    ),
+    (
+      "Repo",
+      metadata.repo.clone(),
+      project.git_repository.clone(),
+    ),
+  ];
+  fields
+    .into_iter()
+    .filter_map(|(field, desired, live)| match desired {
+      Some(desired) if Some(&desired) != live.as_ref() => Some(drift(
+        &key,
+        "project",
+        field,
+        desired,
+        live.unwrap_or_default(),
+      )),
+      _ => None,
+    })
+    .collect()
+}
+
+/// The settings of current deployment different from the deploy request resolved from spec, the
+/// deployment itself if not found
+fn deployment_drifts(
+  key: &str,
+  type_: &DeploymentType,
+  spec: &DeploymentSpec,
+  current: Option<(&Deployment, &CreateDeployRequest)>,
+) -> Vec<SpecDrift> {
+  let resource = type_.as_ref();
+  let (current, desired) = match current {
+    Some(v) => v,
+    None => {
+      return vec![drift(
+        key,
+        resource,
+        "Deployment",
+        spec.branch.clone(),
+        "not found".to_string(),
+      )]
+    }
+  };
+  crate::command::output::deploy_changes(desired, Some(current))
+    .into_iter()
+    .filter(|(_, live, desired)| live != desired)
+    .map(|(field, live, desired)| drift(key, resource, field, desired, live))
+    .collect()
+}
+
+/// Create or update the project, return whether the project exists
+async fn apply_project_spec(
+  subquery: &Subquery,
+  spec: &ProjectSpec,
+  dry_run: bool,
+) -> color_eyre::Result<(ApplyAction, Option<String>, bool)> {
+  let project = subquery.project(spec.project_key()).await?;
+  let (action, message) = apply_project(subquery, spec, project.as_ref(), dry_run).await?;
+  let exists = project.is_some() || action == ApplyAction::Created;
+  Ok((action, message, exists))
+}
+
+/// Deploy or redeploy the stage and primary deployments, only changed deployments are updated.
+async fn apply_deployment_specs(
+  subquery: &Subquery,
+  spec: &ProjectSpec,
+  exists: bool,
+  dry_run: bool,
+  override_policy: bool,
+) -> Vec<ApplyResult> {
+  let key = spec.project_key();
+  let mut results = vec![];
+  let deployments = [
+    (DeploymentType::Stage, spec.stage.as_ref()),
+    (DeploymentType::Primary, spec.primary.as_ref()),
+  ];
+  for (type_, deployment) in deployments {
+    let deployment = match deployment {
+      Some(v) => v,
+      None => continue,
+    };
+    // the project will be created, nothing can be resolved before that
+    let result = if exists {
+      let command = deploy_command(deployment, type_.clone(), override_policy);
+      apply_deployment(subquery, &key, command, dry_run).await
+    } else {
+      Ok((ApplyAction::Planned, Some("deploy".to_string())))
+    };
+    let (action, message) = result.unwrap_or_else(|e| (ApplyAction::Failed, Some(e.to_string())));
+    results.push(ApplyResult {
+      project_key: key.clone(),
+      resource: type_.as_ref().to_string(),
+      action,
+      message,
+    });
+  }
+  results
+}
+
+async fn apply_project(
+  subquery: &Subquery,
+  spec: &ProjectSpec,
+  current: Option<&Project>,
+  dry_run: bool,
+) -> color_eyre::Result<(ApplyAction, Option<String>)> {
+  let key = spec.project_key();
+  let metadata = &spec.project;
+  let current = match current {
+    Some(v) => v,
+    None => {
+      let repo = metadata.repo.clone().ok_or_else(|| {
+        SubqueryError::Custom(format!(
+          "The project {} not found, project.repo is required to create it",
+          key
+        ))
+      })?;
+      if dry_run {
+        return Ok((ApplyAction::Planned, Some("create".to_string())));
+      }
+      let project = Project {
+        api_version: ApiVersion::Latest,
+        created_at: None,
+        updated_at: None,
+        key: key.clone(),
+        account: Some(spec.org.clone()),
+        name: Some(metadata.name.clone().unwrap_or_else(|| spec.key.clone())),
+        network: None,
+        deployed: None,
+        logo_url: None,
+        subtitle: metadata.subtitle.clone(),
+        description: metadata.description.clone(),
+        git_repository: Some(repo),
+        hide: Some(metadata.hide.unwrap_or(true)),
+        dedicate_db_key: None,
+        query_url: None,
+        deployment: None,
+      };
+      subquery.create_project(project).await?;
+      return Ok((ApplyAction::Created, None));
+    }
+  };
+
+  if let Some(repo) = &metadata.repo {
+    if Some(repo) != current.git_repository.as_ref() {
+      tracing::warn!(
+        "The git repository of {} can not be changed, current is {}",
+        key,
+        current.git_repository.clone().unwrap_or_default()
+      );
+    }
+  }
+
+  let changed = |desired: &Option<String>, current: &Option<String>| {
+    desired
+      .as_ref()
+      .filter(|&v| Some(v) != current.as_ref())
+      .cloned()
+  };
+  let project = Project {
+    api_version: ApiVersion::Latest,
+    created_at: None,
+    updated_at: None,
+    key: key.clone(),
+    account: None,
+    name: changed(&metadata.name, &current.name),
+    network: None,
+    deployed: None,
+    logo_url: None,
+    subtitle: changed(&metadata.subtitle, &current.subtitle),
+    description: changed(&metadata.description, &current.description),
+    git_repository: None,
+    hide: metadata.hide.filter(|&v| Some(v) != current.hide),
+    dedicate_db_key: None,
+    query_url: None,
+    deployment: None,
+  };
+  let fields = [
+    ("name", project.name.is_some()),
+    ("subtitle", project.subtitle.is_some()),
+    ("description", project.description.is_some()),
+    ("hide", project.hide.is_some()),
+  ]
+  .iter()
+  .filter(|(_, changed)| *changed)
+  .map(|(name, _)| *name)
+  .collect::<Vec<&str>>();
+  if fields.is_empty() {
+    return Ok((ApplyAction::Unchanged, None));
+  }
+  let message = Some(fields.join(", "));
+  if dry_run {
+    return Ok((ApplyAction::Planned, message));
+  }
+  subquery.update_project(project).await?;
+  Ok((ApplyAction::Updated, message))
+}
+
+async fn apply_deployment(
+  subquery: &Subquery,
+  key: &str,
+  command: DeployCommand,
+  dry_run: bool,
+) -> color_eyre::Result<(ApplyAction, Option<String>)> {
+  let deployments = subquery.deployments(key).await?;
+  let current = deployments.iter().find(|&item| item.type_ == command.type_);
+  let branch = command.branch.clone().unwrap_or_default();
+
+  let deployment = deploy_request(&command, current);
+  let deployment = safe_create_deploy(subquery, deployment, key, Some(&branch)).await?;
+  let changes = crate::command::output::deploy_changes(&deployment, current)
+    .into_iter()
+    .filter(|(_, current, new)| current != new)
+    .map(|(name, _, _)| name)
+    .collect::<Vec<&str>>();
+  if current.is_some() && changes.is_empty() {
+    return Ok((ApplyAction::Unchanged, None));
+  }
+  check_policy(
+    subquery,
+    key,
+    command.branch.as_deref(),
+    command.override_policy,
+    &deployment,
+    &deployments,
+  )
+  .await?;
+  let message = match current {
+    Some(_) => Some(changes.join(", ")),
+    None => Some("deploy".to_string()),
+  };
+  if dry_run {
+    return Ok((ApplyAction::Planned, message));
+  }
+
+  match current {
+    Some(current) => {
+      subquery.redeploy(key, current.id, &deployment).await?;
+      record_history(
+        key,
+        Some(current.id),
+        DeploymentAction::Redeploy,
+        &deployment,
+      );
+      Ok((ApplyAction::Redeployed, message))
+    }
+    None => {
+      let response = subquery.deploy(key, &deployment).await?;
+      record_history(
+        key,
+        response.deployment.as_ref().map(|v| v.id),
+        DeploymentAction::Deploy,
+        &deployment,
+      );
+      Ok((ApplyAction::Deployed, message))
+    }
+  }
+}
+
+/// The deploy command of deployment spec
+fn deploy_command(
+  spec: &DeploymentSpec,
+  type_: DeploymentType,
+  override_policy: bool,
+) -> DeployCommand {
+  DeployCommand {
+    branch: Some(spec.branch.clone()),
+    from_local: false,
+    // a deployment without commit follows the branch head
+    commit: Some(spec.commit.clone().unwrap_or_else(|| "HEAD".to_string())),
+    endpoint: spec.endpoint.clone(),
+    dict_endpoint: spec.dict_endpoint.clone(),
+    indexer_image_version: spec.indexer_image_version.clone(),
+    query_image_version: spec.query_image_version.clone(),
+    type_,
+    sub_folder: spec.sub_folder.clone(),
+    batch_size: spec.batch_size,
+    subscription: spec.subscription == Some(true),
+    no_subscription: spec.subscription == Some(false),
+    override_policy,
+    validate: false,
+    local_path: None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::local::ProjectMetadata;
+  use crate::subquery::fixtures;
+
+  fn spec() -> ProjectSpec {
+    ProjectSpec {
+      org: "fewensa".to_string(),
+      key: "project".to_string(),
+      project: ProjectMetadata::default(),
+      stage: None,
+      primary: None,
+    }
+  }
+
+  fn deployment_spec(commit: &str) -> DeploymentSpec {
+    DeploymentSpec {
+      branch: "master".to_string(),
+      commit: Some(commit.to_string()),
+      indexer_image_version: None,
+      query_image_version: None,
+      endpoint: None,
+      dict_endpoint: None,
+      sub_folder: None,
+      batch_size: None,
+      subscription: None,
+      metadata: None,
+    }
+  }
+
+  fn fields(drifts: &[SpecDrift]) -> Vec<(&str, &str, &str, &str)> {
+    drifts
+      .iter()
+      .map(|item| {
+        (
+          item.resource.as_str(),
+          item.field.as_str(),
+          item.desired.as_str(),
+          item.live.as_str(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_project_not_found() {
+    let drifts = project_drifts(&spec(), None);
+    assert_eq!(
+      fields(&drifts),
+      vec![("project", "Project", "fewensa/project", "not found")]
+    );
+  }
+
+  #[test]
+  fn test_project_drifts() {
+    let project = fixtures::project("fewensa/project");
+    assert!(project_drifts(&spec(), Some(&project)).is_empty());
+
+    let mut spec = spec();
+    spec.project = ProjectMetadata {
+      name: Some("Renamed".to_string()),
+      subtitle: Some("Subtitle".to_string()),
+      description: Some("The project".to_string()),
+      hide: Some(false),
+      repo: None,
+    };
+    let drifts = project_drifts(&spec, Some(&project));
+    assert_eq!(
+      fields(&drifts),
+      vec![
+        ("project", "Name", "Renamed", "Project"),
+        ("project", "Subtitle", "Subtitle", ""),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_deployment_not_found() {
+    let drifts = deployment_drifts(
+      "fewensa/project",
+      &DeploymentType::Primary,
+      &deployment_spec("c"),
+      None,
+    );
+    assert_eq!(
+      fields(&drifts),
+      vec![("primary", "Deployment", "master", "not found")]
+    );
+  }
+
+  #[test]
+  fn test_deployment_drifts() {
+    let current = fixtures::deployment("stage", "c");
+    let drifts_of = |spec: &DeploymentSpec| {
+      let command = deploy_command(spec, DeploymentType::Stage, false);
+      let desired = deploy_request(&command, Some(&current));
+      deployment_drifts(
+        "fewensa/project",
+        &DeploymentType::Stage,
+        spec,
+        Some((&current, &desired)),
+      )
+    };
+    // the settings not set in spec keep the current value
+    assert!(drifts_of(&deployment_spec("c")).is_empty());
+
+    let mut spec = deployment_spec("d");
+    spec.batch_size = Some(50);
+    spec.indexer_image_version = Some("v1.0.0".to_string());
+    assert_eq!(
+      fields(&drifts_of(&spec)),
+      vec![
+        ("stage", "Commit", "d", "c"),
+        ("stage", "Indexer image version", "v1.0.0", "v0.25.3"),
+        ("stage", "Batch size", "50", "30"),
+      ]
+    );
+  }
+}
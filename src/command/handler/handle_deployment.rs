@@ -1,103 +1,471 @@
-use crate::command::types::{DeploymentOpt, OutputFormat};
-use crate::subquery::{AdvancedSettings, CreateDeployRequest, DeploymentType};
+use futures::StreamExt;
+
+use crate::command::output::{
+  ActionResult, Changelog, OutdatedDeployment, UpgradeResult, UpgradeStatus,
+};
+use crate::command::types::{
+  DeployCommand, DeploymentOpt, Output, OutputFormat, TableOpt, UpgradeCommand,
+};
+use crate::local::{
+  self, DeploymentAction, DeploymentHistory, DeploymentRecord, DeploymentSnapshot, LocalConfig,
+  LocalRepository, PolicyContext,
+};
+use crate::subquery::{
+  commit, image, AdvancedSettings, Branch, CreateDeployRequest, Deployment, DeploymentType,
+  SyncStatus, DEFAULT_BATCH_SIZE,
+};
 use crate::{Subquery, SubqueryError};
 
-pub async fn handle_deployment(subquery: &Subquery, opt: DeploymentOpt) -> color_eyre::Result<()> {
+pub async fn handle_deployment(
+  subquery: &Subquery,
+  opt: DeploymentOpt,
+  output: &Output,
+) -> color_eyre::Result<()> {
   match opt {
-    DeploymentOpt::List { org, key, output } => {
-      handle_list(subquery, format!("{}/{}", org, key), output).await
+    DeploymentOpt::List { org, key, table } => {
+      handle_list(subquery, format!("{}/{}", org, key), output, table).await
     }
     DeploymentOpt::Deploy {
       org,
       key,
-      output,
       command,
       force,
+      dry_run,
     } => {
-      let deployment = CreateDeployRequest {
-        commit: command.commit,
-        endpoint: command.endpoint,
-        dict_endpoint: command.dict_endpoint,
-        indexer_image_version: command.indexer_image_version,
-        query_image_version: command.query_image_version,
-        type_: command.type_,
-        sub_folder: command.sub_folder,
-        advanced_settings: AdvancedSettings::new(command.batch_size, command.subscription),
-      };
-      handle_deploy(
-        subquery,
-        format!("{}/{}", org, key),
-        command.branch,
-        deployment,
-        output,
-        force,
-      )
-      .await
+      let key = format!("{}/{}", org, key);
+      let command = deploy_from_local(subquery, &key, command).await?;
+      handle_deploy(subquery, key, command, output, force, dry_run).await
     }
     DeploymentOpt::Delete { org, key, id } => {
-      handle_delete(subquery, format!("{}/{}", org, key), id).await
+      handle_delete(subquery, format!("{}/{}", org, key), id, output).await
     }
     DeploymentOpt::Redeploy {
       org,
       key,
       id,
       command,
+      dry_run,
     } => {
-      let deployment = CreateDeployRequest {
-        commit: command.commit,
-        endpoint: command.endpoint,
-        dict_endpoint: command.dict_endpoint,
-        indexer_image_version: command.indexer_image_version,
-        query_image_version: command.query_image_version,
-        type_: command.type_,
-        sub_folder: command.sub_folder,
-        advanced_settings: AdvancedSettings::new(command.batch_size, command.subscription),
-      };
-      handle_redeploy(
+      let key = format!("{}/{}", org, key);
+      let command = deploy_from_local(subquery, &key, command).await?;
+      handle_redeploy(subquery, key, id, command, output, dry_run).await
+    }
+    DeploymentOpt::Promote {
+      org,
+      key,
+      id,
+      override_policy,
+    } => {
+      handle_promote(
         subquery,
         format!("{}/{}", org, key),
-        command.branch,
         id,
-        deployment,
+        override_policy,
+        output,
       )
       .await
     }
-    DeploymentOpt::Promote { org, key, id } => {
-      handle_promote(subquery, format!("{}/{}", org, key), id).await
+    DeploymentOpt::Rollback {
+      org,
+      key,
+      type_,
+      dry_run,
+      override_policy,
+    } => {
+      handle_rollback(
+        subquery,
+        format!("{}/{}", org, key),
+        type_,
+        dry_run,
+        override_policy,
+        output,
+      )
+      .await
     }
+    DeploymentOpt::Restore {
+      snapshot,
+      dry_run,
+      override_policy,
+    } => handle_restore(subquery, snapshot, dry_run, override_policy, output).await,
+    DeploymentOpt::Changelog {
+      org,
+      key,
+      type_,
+      branch,
+      markdown,
+    } => {
+      handle_changelog(
+        subquery,
+        format!("{}/{}", org, key),
+        type_,
+        branch,
+        markdown,
+        output,
+      )
+      .await
+    }
+    DeploymentOpt::Diff { org, key } => {
+      handle_diff(subquery, format!("{}/{}", org, key), output).await
+    }
+    DeploymentOpt::Outdated {
+      org,
+      all,
+      concurrency,
+    } => handle_outdated(subquery, org, all, concurrency, output).await,
+    DeploymentOpt::Upgrade { org, command } => handle_upgrade(subquery, org, command, output).await,
     DeploymentOpt::SyncStatus {
       org,
       key,
       id,
       rolling,
       interval,
-    } => handle_sync_status(subquery, format!("{}/{}", org, key), id, rolling, interval).await,
+    } => {
+      handle_sync_status(
+        subquery,
+        format!("{}/{}", org, key),
+        id,
+        rolling,
+        interval,
+        output,
+      )
+      .await
+    }
   }
 }
 
+async fn handle_changelog(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  type_: DeploymentType,
+  branch: String,
+  markdown: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let deployments = subquery.deployments(key).await?;
+  let current = deployments.iter().find(|&item| item.type_ == type_);
+  let mut commits = subquery.commit(key, &branch).await?;
+  if let Some(current) = current {
+    // the branch history only has the latest commits, the deployed commit may be older
+    match commits.iter().position(|item| item.sha == current.version) {
+      Some(position) => commits.truncate(position),
+      None => tracing::warn!(
+        "The deployed commit {} is older than the latest {} commits of branch {}, the changelog is truncated",
+        current.version,
+        commits.len(),
+        branch
+      ),
+    }
+  }
+  let changelog = Changelog {
+    project_key: key.to_string(),
+    type_,
+    branch,
+    from: current.map(|v| v.version.clone()),
+    to: commits.first().map(|v| v.sha.clone()),
+    commits,
+  };
+  if markdown {
+    return crate::command::output::output_changelog_markdown(changelog);
+  }
+  crate::command::output::output_changelog(changelog, output)
+}
+
+async fn handle_diff(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let deployments = subquery.deployments(key).await?;
+  let primary = deployments
+    .iter()
+    .find(|&item| item.type_ == DeploymentType::Primary);
+  let stage = deployments
+    .iter()
+    .find(|&item| item.type_ == DeploymentType::Stage);
+  let primary_status = match primary {
+    Some(v) => try_sync_status(subquery, key, v.id).await,
+    None => None,
+  };
+  let stage_status = match stage {
+    Some(v) => try_sync_status(subquery, key, v.id).await,
+    None => None,
+  };
+  crate::command::output::output_deployment_diff(
+    (primary, primary_status.as_ref()),
+    (stage, stage_status.as_ref()),
+    output,
+  )
+}
+
+/// The sync status is not available when the deployment is not running, treat it as unknown.
+async fn try_sync_status(subquery: &Subquery, key: &str, id: u64) -> Option<SyncStatus> {
+  match subquery.deployment_sync_status(key, id).await {
+    Ok(v) => Some(v),
+    Err(e) => {
+      tracing::warn!("Failed to query sync status for deployment {}: {}", id, e);
+      None
+    }
+  }
+}
+
+async fn handle_outdated(
+  subquery: &Subquery,
+  org: String,
+  all: bool,
+  concurrency: usize,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let node_tags = subquery.image("@subql/node").await?;
+  let query_tags = subquery.image("@subql/query").await?;
+  let latest_node = image::resolve_image_version("@subql/node", &node_tags, None)?;
+  let latest_query = image::resolve_image_version("@subql/query", &query_tags, None)?;
+
+  let projects = subquery.projects(org).await?;
+  let mut results = futures::stream::iter(projects)
+    .map(|project| async move {
+      let deployments = subquery.deployments(&project.key).await;
+      (project.key, deployments)
+    })
+    .buffer_unordered(concurrency.max(1))
+    .collect::<Vec<_>>()
+    .await;
+  results.sort_by(|a, b| a.0.cmp(&b.0));
+
+  let mut outdated = vec![];
+  for (key, deployments) in results {
+    let deployments = match deployments {
+      Ok(v) => v,
+      Err(e) => {
+        eprintln!("Failed to query deployments of {}: {}", key, e);
+        continue;
+      }
+    };
+    for deployment in deployments {
+      let indexer_version = deployment.indexer_image_version().unwrap_or_default();
+      let query_version = deployment.query_image_version().unwrap_or_default();
+      let item = OutdatedDeployment {
+        project_key: key.clone(),
+        id: deployment.id,
+        type_: deployment.type_.clone(),
+        indexer_image_version: indexer_version.to_string(),
+        indexer_behind: image::image_versions_behind(&node_tags, indexer_version),
+        latest_indexer_image_version: latest_node.clone(),
+        query_image_version: query_version.to_string(),
+        query_behind: image::image_versions_behind(&query_tags, query_version),
+        latest_query_image_version: latest_query.clone(),
+      };
+      if all || item.is_outdated() {
+        outdated.push(item);
+      }
+    }
+  }
+  crate::command::output::output_outdated(outdated, output)
+}
+
+async fn handle_upgrade(
+  subquery: &Subquery,
+  org: String,
+  command: UpgradeCommand,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  if command.indexer_image_version.is_none() && command.query_image_version.is_none() {
+    return Err(
+      SubqueryError::Custom(
+        "Please set --indexer-image-version or --query-image-version".to_string(),
+      )
+      .into(),
+    );
+  }
+  let pattern = match &command.projects {
+    Some(v) => Some(
+      glob::Pattern::new(v)
+        .map_err(|e| SubqueryError::Custom(format!("Wrong projects pattern {}: {}", v, e)))?,
+    ),
+    None => None,
+  };
+  let indexer_image_version = match &command.indexer_image_version {
+    Some(v) => Some(resolve_image(subquery, "@subql/node", Some(v)).await?),
+    None => None,
+  };
+  let query_image_version = match &command.query_image_version {
+    Some(v) => Some(resolve_image(subquery, "@subql/query", Some(v)).await?),
+    None => None,
+  };
+
+  let projects = subquery.projects(org).await?;
+  let keys = projects
+    .into_iter()
+    .map(|project| project.key)
+    .filter(|key| match &pattern {
+      Some(pattern) => {
+        pattern.matches(key) || pattern.matches(key.split('/').next_back().unwrap_or_default())
+      }
+      None => true,
+    })
+    .collect::<Vec<String>>();
+
+  let command = &command;
+  let indexer_image_version = indexer_image_version.as_deref();
+  let query_image_version = query_image_version.as_deref();
+  let mut results = futures::stream::iter(keys)
+    .map(|key| async move {
+      let result = upgrade_deployment(
+        subquery,
+        &key,
+        &command.type_,
+        indexer_image_version,
+        query_image_version,
+        command.dry_run,
+        command.override_policy,
+      )
+      .await;
+      match result {
+        Ok(v) => v,
+        Err(e) => UpgradeResult {
+          project_key: key,
+          id: None,
+          indexer_image_version: None,
+          query_image_version: None,
+          status: UpgradeStatus::Failed,
+          message: Some(e.to_string()),
+        },
+      }
+    })
+    .buffer_unordered(command.concurrency.max(1))
+    .collect::<Vec<UpgradeResult>>()
+    .await;
+  results.sort_by(|a, b| a.project_key.cmp(&b.project_key));
+
+  let failed = results
+    .iter()
+    .filter(|item| item.status == UpgradeStatus::Failed)
+    .count();
+  crate::command::output::output_upgrade(results, output)?;
+  if failed > 0 {
+    return Err(SubqueryError::Custom(format!("{} projects failed to upgrade", failed)).into());
+  }
+  Ok(())
+}
+
+/// Redeploy the deployment of this type with new images, keep the commit and settings.
+async fn upgrade_deployment(
+  subquery: &Subquery,
+  key: &str,
+  type_: &DeploymentType,
+  indexer_image_version: Option<&str>,
+  query_image_version: Option<&str>,
+  dry_run: bool,
+  override_policy: bool,
+) -> color_eyre::Result<UpgradeResult> {
+  let deployments = subquery.deployments(key).await?;
+  let current = match deployments.iter().find(|&item| &item.type_ == type_) {
+    Some(v) => v,
+    None => {
+      return Ok(UpgradeResult {
+        project_key: key.to_string(),
+        id: None,
+        indexer_image_version: None,
+        query_image_version: None,
+        status: UpgradeStatus::Skipped,
+        message: Some(format!("Not found any deploy for type: {:?}", type_)),
+      });
+    }
+  };
+
+  let mut deployment = CreateDeployRequest::from_deployment(current);
+  if let Some(v) = indexer_image_version {
+    deployment.indexer_image_version = Some(v.to_string());
+  }
+  if let Some(v) = query_image_version {
+    deployment.query_image_version = Some(v.to_string());
+  }
+  let changes = |current: Option<&str>, new: &Option<String>| {
+    Some(format!(
+      "{} -> {}",
+      current.unwrap_or_default(),
+      new.clone().unwrap_or_default()
+    ))
+  };
+  let mut result = UpgradeResult {
+    project_key: key.to_string(),
+    id: Some(current.id),
+    indexer_image_version: changes(
+      current.indexer_image_version(),
+      &deployment.indexer_image_version,
+    ),
+    query_image_version: changes(
+      current.query_image_version(),
+      &deployment.query_image_version,
+    ),
+    status: UpgradeStatus::Upgraded,
+    message: None,
+  };
+
+  let unchanged = current.indexer_image_version() == deployment.indexer_image_version.as_deref()
+    && current.query_image_version() == deployment.query_image_version.as_deref();
+  if unchanged {
+    result.status = UpgradeStatus::Unchanged;
+    return Ok(result);
+  }
+  check_policy(
+    subquery,
+    key,
+    None,
+    override_policy,
+    &deployment,
+    &deployments,
+  )
+  .await?;
+  if dry_run {
+    result.status = UpgradeStatus::Planned;
+    return Ok(result);
+  }
+  subquery.redeploy(key, current.id, &deployment).await?;
+  record_history(
+    key,
+    Some(current.id),
+    DeploymentAction::Upgrade,
+    &deployment,
+  );
+  Ok(result)
+}
+
 async fn handle_sync_status(
   subquery: &Subquery,
   key: impl AsRef<str>,
   id: u64,
   rolling: bool,
   interval: u64,
+  output: &Output,
 ) -> color_eyre::Result<()> {
   let mut times = 0usize;
   loop {
     times += 1;
     let status = subquery.deployment_sync_status(key.as_ref(), id).await?;
-    let percent = (status.processing_block as f32 / status.target_block as f32) * 100f32;
-    println!(
-      "target_block: {} processing_block: {} percent: {:.2}%{} ",
-      status.target_block,
-      status.processing_block,
-      percent,
-      if rolling {
-        format!(" [{}]", times)
-      } else {
-        "".to_string()
-      },
-    );
+    let percent = status.percent();
+    if crate::command::output::is_structured(output) {
+      let status = serde_json::json!({
+        "id": id,
+        "targetBlock": status.target_block,
+        "processingBlock": status.processing_block,
+        "percent": percent,
+      });
+      crate::command::output::output_structured(&status, output)?;
+    } else {
+      println!(
+        "target_block: {} processing_block: {} percent: {:.2}%{} ",
+        status.target_block,
+        status.processing_block,
+        percent,
+        if rolling {
+          format!(" [{}]", times)
+        } else {
+          "".to_string()
+        },
+      );
+    }
     if !rolling {
       break;
     }
@@ -110,59 +478,253 @@ async fn handle_promote(
   subquery: &Subquery,
   key: impl AsRef<str>,
   id: Option<u64>,
+  override_policy: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let deployments = subquery.deployments(key).await?;
+  let stage_deployment = match id {
+    Some(id) => deployments.iter().find(|&item| item.id == id),
+    None => deployments
+      .iter()
+      .find(|&item| item.type_ == DeploymentType::Stage),
+  };
+  let stage_deployment = match stage_deployment {
+    Some(v) => v,
+    None => {
+      let message = match id {
+        Some(id) => format!("Not found deployment by id: {}", id),
+        None => "Not found any stage deployment".to_string(),
+      };
+      return Err(SubqueryError::Custom(message).into());
+    }
+  };
+  let mut deployment = CreateDeployRequest::from_deployment(stage_deployment);
+  deployment.type_ = DeploymentType::Primary;
+  check_policy(
+    subquery,
+    key,
+    None,
+    override_policy,
+    &deployment,
+    &deployments,
+  )
+  .await?;
+  subquery.rebase_deployment(key, stage_deployment.id).await?;
+  record_history(
+    key,
+    Some(stage_deployment.id),
+    DeploymentAction::Promote,
+    &deployment,
+  );
+  let result = ActionResult::new("promote", key, Some(stage_deployment.id));
+  crate::command::output::output_action(result, output)
+}
+
+async fn handle_rollback(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  type_: DeploymentType,
+  dry_run: bool,
+  override_policy: bool,
+  output: &Output,
 ) -> color_eyre::Result<()> {
   let key = key.as_ref();
-  if id.is_some() {
-    let _ = subquery.rebase_deployment(key, id.unwrap()).await?;
-    println!("Success");
-    return Ok(());
-  }
   let deployments = subquery.deployments(key).await?;
-  let latest_stage_deployment = deployments
+  let current = deployments.iter().find(|&item| item.type_ == type_);
+  let records = DeploymentHistory::new()?.records(key, &type_)?;
+  let previous = local::rollback_target(&records, |record| match current {
+    Some(current) => record.same_version(
+      &current.version,
+      current.indexer_image_version(),
+      current.query_image_version(),
+    ),
+    None => false,
+  });
+  let previous = previous.ok_or_else(|| {
+    SubqueryError::Custom(format!(
+      "Not found any previous {:?} deployment of {} in local history",
+      type_, key
+    ))
+  })?;
+  let deployment = previous.deployment.clone();
+  tracing::info!(
+    "Rollback to the deployment recorded at {}, commit: {}",
+    previous.time,
+    deployment.commit.clone().unwrap_or_default()
+  );
+  apply_deploy_request(
+    subquery,
+    key,
+    deployment,
+    &deployments,
+    DeploymentAction::Rollback,
+    dry_run,
+    override_policy,
+    output,
+  )
+  .await
+}
+
+async fn handle_restore(
+  subquery: &Subquery,
+  snapshot: String,
+  dry_run: bool,
+  override_policy: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let snapshot = DeploymentSnapshot::load(snapshot)?;
+  let key = &snapshot.project_key;
+  let deployment = CreateDeployRequest::from_deployment(&snapshot.deployment);
+  let deployments = subquery.deployments(key).await?;
+  tracing::info!(
+    "Restore deployment {} of {} saved at {}",
+    snapshot.deployment.id,
+    key,
+    snapshot.time
+  );
+  apply_deploy_request(
+    subquery,
+    key,
+    deployment,
+    &deployments,
+    DeploymentAction::Restore,
+    dry_run,
+    override_policy,
+    output,
+  )
+  .await
+}
+
+/// Redeploy the current deployment with the request, or create a new deployment if not exists.
+#[allow(clippy::too_many_arguments)]
+async fn apply_deploy_request(
+  subquery: &Subquery,
+  key: &str,
+  deployment: CreateDeployRequest,
+  deployments: &[Deployment],
+  action: DeploymentAction,
+  dry_run: bool,
+  override_policy: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  check_policy(
+    subquery,
+    key,
+    None,
+    override_policy,
+    &deployment,
+    deployments,
+  )
+  .await?;
+  let current = deployments
     .iter()
-    .find(|&item| item.type_ == DeploymentType::Stage);
-  if let Some(deployment) = latest_stage_deployment {
-    let _ = subquery.rebase_deployment(key, deployment.id).await?;
-    println!("Success");
-    return Ok(());
+    .find(|&item| item.type_ == deployment.type_);
+  match current {
+    Some(current) => {
+      if dry_run {
+        let plan = format!("Redeploy {}", current.id);
+        return crate::command::output::output_deploy_plan(
+          plan,
+          &deployment,
+          Some(current),
+          output,
+        );
+      }
+      subquery.redeploy(key, current.id, &deployment).await?;
+      record_history(key, Some(current.id), action.clone(), &deployment);
+      let result = ActionResult::new(action.as_ref(), key, Some(current.id));
+      crate::command::output::output_action(result, output)
+    }
+    None => {
+      if dry_run {
+        return crate::command::output::output_deploy_plan("Create", &deployment, None, output);
+      }
+      let response = subquery.deploy(key, &deployment).await?;
+      record_history(
+        key,
+        response.deployment.as_ref().map(|v| v.id),
+        action,
+        &deployment,
+      );
+      crate::command::output::output_project(response, output)
+    }
+  }
+}
+
+/// Record the deployment to local history, the failure of recording will not break the command.
+pub(crate) fn record_history(
+  key: &str,
+  id: Option<u64>,
+  action: DeploymentAction,
+  deployment: &CreateDeployRequest,
+) {
+  let record = DeploymentRecord::new(key, id, action, deployment.clone());
+  if let Err(e) = DeploymentHistory::new().and_then(|history| history.append(&record)) {
+    tracing::warn!("Failed to record deployment history: {}", e);
   }
-  eprintln!("Not found any stage deployment");
-  Ok(())
 }
 
 async fn handle_redeploy(
   subquery: &Subquery,
   key: impl AsRef<str>,
-  branch: impl AsRef<str>,
   id: Option<u64>,
-  mut deployment: CreateDeployRequest,
+  command: DeployCommand,
+  output: &Output,
+  dry_run: bool,
 ) -> color_eyre::Result<()> {
   let key = key.as_ref();
-  if id.is_some() {
-    deployment = safe_create_deploy(subquery, deployment, key, branch).await?;
-    let _response = subquery.redeploy(key, id.unwrap(), &deployment).await?;
-    println!("Success");
-    return Ok(());
-  }
-
-  let type_ = &deployment.type_;
   let deployments = subquery.deployments(key).await?;
-  let this_type_latest_deployment = deployments.iter().find(|&item| &item.type_ == type_);
-  if let Some(latest) = this_type_latest_deployment {
-    deployment = safe_create_deploy(subquery, deployment, key, branch).await?;
-    let _response = subquery.redeploy(key, latest.id, &deployment).await?;
-    println!("Success");
-    return Ok(());
+  let current = match id {
+    Some(id) => deployments.iter().find(|&item| item.id == id),
+    None => deployments.iter().find(|&item| item.type_ == command.type_),
+  };
+  let current = match current {
+    Some(v) => v,
+    None => {
+      let message = match id {
+        Some(id) => format!("Not found deployment by id: {}", id),
+        None => format!("Not found any deploy for type: {:?}", command.type_),
+      };
+      return Err(SubqueryError::Custom(message).into());
+    }
+  };
+
+  let deployment = deploy_request(&command, Some(current));
+  validate_manifest(&command, &deployment)?;
+  let branch = command.branch.as_deref();
+  let deployment = safe_create_deploy(subquery, deployment, key, branch).await?;
+  check_policy(
+    subquery,
+    key,
+    branch,
+    command.override_policy,
+    &deployment,
+    &deployments,
+  )
+  .await?;
+  if dry_run {
+    let action = format!("Redeploy {}", current.id);
+    return crate::command::output::output_deploy_plan(action, &deployment, Some(current), output);
   }
-  eprintln!("Not found any deploy for type: {:?}", type_);
-  std::process::exit(1)
+  subquery.redeploy(key, current.id, &deployment).await?;
+  record_history(
+    key,
+    Some(current.id),
+    DeploymentAction::Redeploy,
+    &deployment,
+  );
+  let result = ActionResult::new("redeploy", key, Some(current.id));
+  crate::command::output::output_action(result, output)
 }
 
 async fn handle_delete(
   subquery: &Subquery,
   key: impl AsRef<str>,
   id: u64,
+  output: &Output,
 ) -> color_eyre::Result<()> {
+  let result = ActionResult::new("delete_deploy", key.as_ref(), Some(id));
   let question = requestty::Question::expand("delete")
     .message("Are you sure delete this deployment?")
     .choices(vec![('y', "Yes"), ('n', "No")])
@@ -172,10 +734,10 @@ async fn handle_delete(
   let answer = requestty::prompt_one(question)?;
   if let Some(v) = answer.as_expand_item() {
     if v.key != 'y' {
-      return Ok(());
+      return crate::command::output::output_action(result.unchanged(), output);
     }
     let _response = subquery.delete_deploy(key, id).await?;
-    println!("Success");
+    return crate::command::output::output_action(result, output);
   }
   Ok(())
 }
@@ -183,111 +745,388 @@ async fn handle_delete(
 async fn handle_list(
   subquery: &Subquery,
   key: impl AsRef<str>,
-  output: OutputFormat,
+  output: &Output,
+  table: TableOpt,
 ) -> color_eyre::Result<()> {
   let deployments = subquery.deployments(key).await?;
-  crate::command::output::output_deployment(deployments, output)?;
-  Ok(())
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&deployments, output);
+  }
+  match output.format {
+    OutputFormat::Table => crate::command::output::output_deployment_table(deployments, &table),
+    _ => crate::command::output::output_deployment(deployments, output),
+  }
 }
 
 async fn handle_deploy(
   subquery: &Subquery,
   key: impl AsRef<str>,
-  branch: impl AsRef<str>,
-  mut deployment: CreateDeployRequest,
-  output: OutputFormat,
+  command: DeployCommand,
+  output: &Output,
   force: bool,
+  dry_run: bool,
 ) -> color_eyre::Result<()> {
   let key = key.as_ref();
-  let branch = branch.as_ref();
 
-  // force, delete old deployment and create again
-  if force {
-    let deployments = subquery.deployments(key).await?;
-    let this_type_deployment = deployments
-      .iter()
-      .find(|&item| item.type_ == deployment.type_);
-    if let Some(deployment) = this_type_deployment {
-      tracing::info!("In force mode, delete deploy for id {}", deployment.id);
-      subquery.delete_deploy(key, deployment.id).await?;
+  let deployments = subquery.deployments(key).await?;
+  let this_type_latest_deployment = deployments.iter().find(|&item| item.type_ == command.type_);
+
+  let branch = deploy_branch(&command)?;
+  // if the deployment is exists and not in force mode, redeploy it with the branch head.
+  if this_type_latest_deployment.is_some() && !force {
+    let mut command = command;
+    if command.commit.is_none() {
+      command.commit = Some("HEAD".to_string());
     }
-    deployment = safe_create_deploy(subquery, deployment, key, branch).await?;
-    let response = subquery.deploy(key, &deployment).await?;
-    return crate::command::output::output_project(response, output);
+    return handle_redeploy(subquery, key, None, command, output, dry_run).await;
   }
 
-  let type_ = &deployment.type_;
-  let deployments = subquery.deployments(key).await?;
-  let this_type_latest_deployment = deployments.iter().find(|&item| &item.type_ == type_);
+  let deployment = deploy_request(&command, None);
+  validate_manifest(&command, &deployment)?;
+  let deployment = safe_create_deploy(subquery, deployment, key, Some(branch)).await?;
+  check_policy(
+    subquery,
+    key,
+    Some(branch),
+    command.override_policy,
+    &deployment,
+    &deployments,
+  )
+  .await?;
+  if dry_run {
+    let action = match this_type_latest_deployment {
+      Some(current) => format!("Replace {}", current.id),
+      None => "Create".to_string(),
+    };
+    return crate::command::output::output_deploy_plan(
+      action,
+      &deployment,
+      this_type_latest_deployment,
+      output,
+    );
+  }
 
-  // if the deployment is exists, redeploy it.
-  if this_type_latest_deployment.is_some() {
-    return handle_redeploy(subquery, key, branch, None, deployment).await;
+  // force, delete old deployment and create again
+  if let Some(current) = this_type_latest_deployment {
+    let snapshot = DeploymentSnapshot::new(key, current.clone()).save()?;
+    tracing::info!(
+      "In force mode, the snapshot of deployment {} is saved to {}",
+      current.id,
+      snapshot.to_string_lossy()
+    );
+    tracing::info!("In force mode, delete deploy for id {}", current.id);
+    subquery.delete_deploy(key, current.id).await?;
   }
 
-  // create deployment directly
-  deployment = safe_create_deploy(subquery, deployment, key, branch).await?;
+  // create deployment
   let response = subquery.deploy(key, &deployment).await?;
+  record_history(
+    key,
+    response.deployment.as_ref().map(|v| v.id),
+    DeploymentAction::Deploy,
+    &deployment,
+  );
   crate::command::output::output_project(response, output)
 }
 
-async fn safe_create_deploy(
+/// The branch of deploy, it's filled from local checkout when deploy from local
+fn deploy_branch(command: &DeployCommand) -> color_eyre::Result<&str> {
+  command
+    .branch
+    .as_deref()
+    .ok_or_else(|| SubqueryError::Custom("Missing --branch".to_string()).into())
+}
+
+/// Fill branch and commit from the git checkout of current directory if deploy from local, the
+/// remote must be the project repository and HEAD must be pushed to the branch.
+async fn deploy_from_local(
+  subquery: &Subquery,
+  key: &str,
+  mut command: DeployCommand,
+) -> color_eyre::Result<DeployCommand> {
+  if !command.from_local {
+    return Ok(command);
+  }
+  let local = LocalRepository::current()?;
+  if local.dirty {
+    tracing::warn!("The local checkout has uncommitted changes, they will not be deployed");
+  }
+
+  let project = subquery
+    .project(key)
+    .await?
+    .ok_or_else(|| SubqueryError::Custom(format!("The project {} not found", key)))?;
+  let repository = project.git_repository.unwrap_or_default();
+  let expected = local::repository_name(&repository);
+  if expected.is_none() || expected != local::repository_name(&local.remote_url) {
+    return Err(
+      SubqueryError::Custom(format!(
+        "The local remote {} is not the project repository {}",
+        local.remote_url, repository
+      ))
+      .into(),
+    );
+  }
+
+  let commits = subquery.commit(key, &local.branch).await?;
+  if !commits.iter().any(|item| item.sha == local.head) {
+    return Err(
+      SubqueryError::Custom(format!(
+        "The local HEAD {} is not pushed to branch {} of {}",
+        local.head, local.branch, repository
+      ))
+      .into(),
+    );
+  }
+  tracing::info!(
+    "Deploy from local branch {} commit {}",
+    local.branch,
+    local.head
+  );
+  command.branch = Some(local.branch);
+  command.commit = Some(local.head);
+  Ok(command)
+}
+
+/// Validate the project manifest of local checkout if required, the errors will block the deploy.
+fn validate_manifest(
+  command: &DeployCommand,
+  deployment: &CreateDeployRequest,
+) -> color_eyre::Result<()> {
+  if !command.validate {
+    return Ok(());
+  }
+  let mut dir = match &command.local_path {
+    Some(v) => v.clone(),
+    None => local::git_root().or_else(|_| std::env::current_dir())?,
+  };
+  if let Some(sub_folder) = &deployment.sub_folder {
+    dir = dir.join(sub_folder);
+  }
+  let errors = local::validate_project(
+    &dir,
+    deployment.endpoint.as_deref(),
+    deployment.dict_endpoint.as_deref(),
+  );
+  if errors.is_empty() {
+    tracing::info!("The project manifest in {} is valid", dir.display());
+    return Ok(());
+  }
+  Err(
+    SubqueryError::Custom(format!(
+      "Invalid project in {}:\n  {}",
+      dir.display(),
+      errors.join("\n  ")
+    ))
+    .into(),
+  )
+}
+
+/// Check the deploy policy configured in local config file, the violations will block the deploy
+/// unless the policy is overridden. the branch is none if not deploy from a branch, e.g. promote
+/// and rollback, then the branch which contains the commit is checked.
+pub(crate) async fn check_policy(
+  subquery: &Subquery,
+  key: &str,
+  branch: Option<&str>,
+  override_policy: bool,
+  deployment: &CreateDeployRequest,
+  deployments: &[Deployment],
+) -> color_eyre::Result<()> {
+  let policy = LocalConfig::load()?.policy(key);
+  let primary = deployment.type_ == DeploymentType::Primary;
+  let protected = primary && policy.protected_branch_for_primary.unwrap_or(false);
+  let newer = policy.require_newer_commit.unwrap_or(false);
+  let branches = if protected || (newer && branch.is_none()) {
+    subquery.branches(key).await?
+  } else {
+    vec![]
+  };
+  let branch = match (branch, deployment.commit.as_deref()) {
+    (Some(name), _) => Some(name),
+    (None, Some(commit)) if protected || newer => {
+      let found = commit_branch(subquery, key, &branches, commit).await?;
+      if found.is_none() {
+        tracing::warn!(
+          "The commit {} not found in the latest commits of any branch, the branch policy is not checked",
+          commit
+        );
+      }
+      found.map(|item| item.name.as_str())
+    }
+    _ => None,
+  };
+  let commits = match branch {
+    Some(branch) if newer => subquery.commit(key, branch).await?,
+    _ => vec![],
+  };
+  let context = PolicyContext {
+    deployment,
+    branch: branches
+      .iter()
+      .find(|item| Some(item.name.as_str()) == branch),
+    commits: &commits,
+    deployments,
+  };
+  let violations = policy.check(&context);
+  if violations.is_empty() {
+    return Ok(());
+  }
+  if override_policy {
+    for violation in &violations {
+      tracing::warn!("Override deploy policy: {}", violation);
+    }
+    return Ok(());
+  }
+  Err(
+    SubqueryError::Custom(format!(
+      "Deploy blocked by policy, use --override-policy to deploy anyway: {}",
+      violations.join("; ")
+    ))
+    .into(),
+  )
+}
+
+/// The branch which contains the commit, protected branches are searched first. the deployment
+/// doesn't record the branch, so search branch heads first and then the latest commits of each
+/// branch.
+pub(crate) async fn commit_branch<'a>(
+  subquery: &Subquery,
+  key: &str,
+  branches: &'a [Branch],
+  sha: &str,
+) -> color_eyre::Result<Option<&'a Branch>> {
+  let mut ordered = branches.iter().collect::<Vec<&Branch>>();
+  ordered.sort_by_key(|item| !item.protected);
+  if let Some(branch) = ordered.iter().find(|item| item.commit.sha == sha) {
+    return Ok(Some(branch));
+  }
+  for branch in ordered {
+    let commits = subquery.commit(key, &branch.name).await?;
+    if commits.iter().any(|item| item.sha == sha) {
+      return Ok(Some(branch));
+    }
+  }
+  Ok(None)
+}
+
+/// Build deploy request from command. if the current deployment is given, the commit and settings
+/// not passed explicitly will keep the current value, otherwise use the default value.
+pub(crate) fn deploy_request(
+  command: &DeployCommand,
+  current: Option<&Deployment>,
+) -> CreateDeployRequest {
+  let batch_size = command
+    .batch_size
+    .or_else(|| current.and_then(|v| v.batch_size()))
+    .unwrap_or(DEFAULT_BATCH_SIZE);
+  let subscription = command
+    .subscription()
+    .or_else(|| current.and_then(|v| v.subscription()))
+    .unwrap_or(false);
+  CreateDeployRequest {
+    commit: command
+      .commit
+      .clone()
+      .or_else(|| current.map(|v| v.version.clone())),
+    endpoint: command
+      .endpoint
+      .clone()
+      .or_else(|| current.and_then(|v| v.endpoint.clone())),
+    dict_endpoint: command
+      .dict_endpoint
+      .clone()
+      .or_else(|| current.and_then(|v| v.dict_endpoint.clone())),
+    indexer_image_version: command
+      .indexer_image_version
+      .clone()
+      .or_else(|| current.and_then(|v| v.indexer_image_version().map(|v| v.to_string()))),
+    query_image_version: command
+      .query_image_version
+      .clone()
+      .or_else(|| current.and_then(|v| v.query_image_version().map(|v| v.to_string()))),
+    type_: current
+      .map(|v| v.type_.clone())
+      .unwrap_or_else(|| command.type_.clone()),
+    sub_folder: command
+      .sub_folder
+      .clone()
+      .or_else(|| current.and_then(|v| v.sub_folder.clone())),
+    advanced_settings: AdvancedSettings::new(batch_size, subscription),
+  }
+}
+
+/// Resolve the commit and image versions of deploy request. the branch is only required when the
+/// commit is resolved from branch history, a full sha or @stage/@primary is resolved without it.
+pub(crate) async fn safe_create_deploy(
   subquery: &Subquery,
   mut deployment: CreateDeployRequest,
   key: impl AsRef<str>,
-  branch: impl AsRef<str>,
+  branch: Option<&str>,
 ) -> color_eyre::Result<CreateDeployRequest> {
   // commit
-  if deployment.commit.is_none() {
-    let commits = subquery.commit(key.as_ref(), branch.as_ref()).await?;
-    match commits.first() {
-      Some(c) => {
-        deployment.commit = Some(c.sha.clone());
-      }
-      None => {
+  let reference = deployment.commit.as_deref().unwrap_or("HEAD");
+  let deployments = if reference.starts_with('@') {
+    subquery.deployments(key.as_ref()).await?
+  } else {
+    vec![]
+  };
+  let commits = match branch {
+    _ if reference.starts_with('@') || commit::is_full_sha(reference) => vec![],
+    Some(branch) => {
+      let commits = subquery.commit(key.as_ref(), branch).await?;
+      if commits.is_empty() {
         let project = subquery.project(key.as_ref()).await?.ok_or_else(|| {
           SubqueryError::Custom(format!("The project {} not found", key.as_ref()))
         })?;
         let msg = format!(
           "No commit found in git repository {}#{}",
           project.git_repository.unwrap_or_default(),
-          branch.as_ref()
+          branch
         );
         return Err(SubqueryError::Custom(msg).into());
       }
+      commits
     }
-  }
+    None => {
+      return Err(
+        SubqueryError::Custom(format!("Missing --branch to resolve commit {}", reference)).into(),
+      )
+    }
+  };
+  deployment.commit = Some(commit::resolve_commit(&commits, &deployments, reference)?);
 
   // image version
-  let not_set_image =
-    deployment.query_image_version.is_none() || deployment.indexer_image_version.is_none();
-  // let mut image = None;
-  let mut image_tags_node = None;
-  let mut image_tags_query = None;
-  if not_set_image {
-    image_tags_node = Some(subquery.image("@subql/node").await?);
-    image_tags_query = Some(subquery.image("@subql/query").await?);
-  }
-
-  if deployment.query_image_version.is_none() {
-    if image_tags_query.is_none() {
-      return Err(SubqueryError::Custom("Not found query image".to_string()).into());
-    }
-    let image_tags_query = image_tags_query.unwrap();
-    let image = image_tags_query
-      .first()
-      .ok_or_else(|| SubqueryError::Custom("No query image tag found by subquery".to_string()))?;
-    deployment.query_image_version = Some(image.clone());
-  }
-  if deployment.indexer_image_version.is_none() {
-    if image_tags_node.is_none() {
-      return Err(SubqueryError::Custom("Not found indexer image".to_string()).into());
+  deployment.indexer_image_version = Some(
+    resolve_image(
+      subquery,
+      "@subql/node",
+      deployment.indexer_image_version.as_deref(),
+    )
+    .await?,
+  );
+  deployment.query_image_version = Some(
+    resolve_image(
+      subquery,
+      "@subql/query",
+      deployment.query_image_version.as_deref(),
+    )
+    .await?,
+  );
+  Ok(deployment)
+}
+
+/// Resolve image version, only query image tags when the version is not an exact tag.
+async fn resolve_image(
+  subquery: &Subquery,
+  name: &str,
+  version: Option<&str>,
+) -> color_eyre::Result<String> {
+  if let Some(version) = version {
+    if !image::is_image_version_constraint(version) {
+      return Ok(version.to_string());
     }
-    let image_tags_node = image_tags_node.unwrap();
-    let image = image_tags_node
-      .first()
-      .ok_or_else(|| SubqueryError::Custom("No indexer image tag found by subquery".to_string()))?;
-    deployment.indexer_image_version = Some(image.clone());
   }
-  Ok(deployment)
+  let tags = subquery.image(name).await?;
+  image::resolve_image_version(name, &tags, version)
 }
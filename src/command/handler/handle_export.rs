@@ -0,0 +1,138 @@
+use crate::command::handler::commit_branch;
+use crate::command::types::{ExportCommand, Output};
+use crate::local::{DeploymentSpec, ProjectMetadata, ProjectSpec};
+use crate::subquery::{Branch, DeploymentType, Project};
+use crate::{Subquery, SubqueryError};
+
+pub async fn handle_export(
+  subquery: &Subquery,
+  command: ExportCommand,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let projects = if command.all {
+    subquery.projects(command.org.clone()).await?
+  } else {
+    let key = format!(
+      "{}/{}",
+      command.org,
+      command.key.clone().unwrap_or_default()
+    );
+    let project = subquery
+      .project(&key)
+      .await?
+      .ok_or_else(|| SubqueryError::Custom(format!("The project {} not found", key)))?;
+    vec![project]
+  };
+
+  let structured = crate::command::output::is_structured(output);
+  let mut specs = vec![];
+  let mut paths = vec![];
+  for (ix, project) in projects.iter().enumerate() {
+    let spec = export_spec(subquery, &command.org, project).await?;
+    match &command.dir {
+      Some(dir) => {
+        let extension = if command.toml { "toml" } else { "yaml" };
+        let path = dir.join(format!("{}.{}", spec.key, extension));
+        spec.save(&path)?;
+        if structured {
+          paths.push(path.display().to_string());
+        } else {
+          println!("{}", path.display());
+        }
+      }
+      None if structured => specs.push(spec),
+      None => {
+        if command.toml && ix > 0 {
+          // toml has no document separator, split projects by an empty line
+          println!();
+        }
+        print!("{}", spec.to_string(command.toml)?);
+      }
+    }
+  }
+  if !structured {
+    return Ok(());
+  }
+  // structured output writes the written files, or the specs if not write to files
+  match command.dir {
+    Some(_) => crate::command::output::output_structured(&paths, output),
+    None => crate::command::output::output_structured(&specs, output),
+  }
+}
+
+/// The spec of live project, applying it to the project changes nothing
+async fn export_spec(
+  subquery: &Subquery,
+  org: &str,
+  project: &Project,
+) -> color_eyre::Result<ProjectSpec> {
+  let deployments = subquery.deployments(&project.key).await?;
+  let branches = if deployments.is_empty() {
+    vec![]
+  } else {
+    subquery.branches(&project.key).await?
+  };
+  let mut spec = ProjectSpec {
+    org: org.to_string(),
+    key: project
+      .key
+      .split_once('/')
+      .map(|(_, key)| key)
+      .unwrap_or(&project.key)
+      .to_string(),
+    project: ProjectMetadata {
+      name: project.name.clone(),
+      subtitle: project.subtitle.clone(),
+      description: project.description.clone(),
+      hide: project.hide,
+      repo: project.git_repository.clone(),
+    },
+    stage: None,
+    primary: None,
+  };
+  for deployment in &deployments {
+    let deployment_spec = DeploymentSpec {
+      branch: deployed_branch(subquery, &project.key, &branches, &deployment.version).await?,
+      commit: Some(deployment.version.clone()),
+      indexer_image_version: deployment.indexer_image_version().map(|v| v.to_string()),
+      query_image_version: deployment.query_image_version().map(|v| v.to_string()),
+      endpoint: deployment.endpoint.clone(),
+      dict_endpoint: deployment.dict_endpoint.clone(),
+      sub_folder: deployment.sub_folder.clone(),
+      batch_size: deployment.batch_size(),
+      subscription: deployment.subscription(),
+      metadata: deployment.metadata.clone(),
+    };
+    match deployment.type_ {
+      DeploymentType::Stage if spec.stage.is_none() => spec.stage = Some(deployment_spec),
+      DeploymentType::Primary if spec.primary.is_none() => spec.primary = Some(deployment_spec),
+      _ => {}
+    }
+  }
+  Ok(spec)
+}
+
+/// The branch which the deployed commit belongs to, main or master if not found.
+async fn deployed_branch(
+  subquery: &Subquery,
+  key: &str,
+  branches: &[Branch],
+  sha: &str,
+) -> color_eyre::Result<String> {
+  if let Some(branch) = commit_branch(subquery, key, branches, sha).await? {
+    return Ok(branch.name.clone());
+  }
+  let fallback = branches
+    .iter()
+    .find(|item| item.name == "main" || item.name == "master")
+    .or_else(|| branches.first())
+    .map(|item| item.name.clone())
+    .unwrap_or_else(|| "master".to_string());
+  tracing::warn!(
+    "The deployed commit {} of {} not found in any branch, use {}",
+    sha,
+    key,
+    fallback
+  );
+  Ok(fallback)
+}
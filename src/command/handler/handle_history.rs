@@ -0,0 +1,72 @@
+use colored::Colorize;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::{HistoryCommand, Output};
+use crate::local::AuditLog;
+
+pub fn handle_history(command: HistoryCommand, output: &Output) -> color_eyre::Result<()> {
+  let key = command.key.as_ref().map(|key| match &command.org {
+    Some(org) => format!("{}/{}", org, key),
+    None => key.clone(),
+  });
+  let records = AuditLog::new()?.records()?;
+  let mut records = records
+    .into_iter()
+    .rev()
+    .filter(|record| match &key {
+      Some(key) => &record.project_key == key || record.project_key.ends_with(&format!("/{}", key)),
+      None => true,
+    })
+    .filter(|record| match &command.org {
+      Some(org) => record.project_key.starts_with(&format!("{}/", org)),
+      None => true,
+    })
+    .filter(|record| match &command.action {
+      Some(action) => &record.action == action,
+      None => true,
+    })
+    .filter(|record| match &command.user {
+      Some(user) => &record.user == user,
+      None => true,
+    })
+    .take(command.limit)
+    .collect::<Vec<_>>();
+  records.reverse();
+
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&records, output);
+  }
+  if records.is_empty() {
+    println!("Not have any history");
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 40;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Time".bold()),
+    TableCell::new("User".bold()),
+    TableCell::new("Action".bold()),
+    TableCell::new("Project key".bold()),
+    TableCell::new("Result".bold()),
+    TableCell::new("Reason".bold()),
+  ]));
+  for record in records {
+    table.add_row(Row::new(vec![
+      TableCell::new(record.time.format("%Y-%m-%d %H:%M:%S")),
+      TableCell::new(record.user),
+      TableCell::new(record.action),
+      TableCell::new(record.project_key),
+      TableCell::new(match record.error {
+        Some(e) => format!("Failed: {}", e).red(),
+        None => "Success".green(),
+      }),
+      TableCell::new(record.reason.unwrap_or_default()),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
@@ -0,0 +1,33 @@
+use crate::command::types::{ImagesOpt, Output};
+use crate::subquery::image;
+use crate::Subquery;
+
+pub async fn handle_images(
+  subquery: &Subquery,
+  opt: ImagesOpt,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  match opt {
+    ImagesOpt::List { package, stable } => handle_list(subquery, package, stable, output).await,
+  }
+}
+
+async fn handle_list(
+  subquery: &Subquery,
+  package: String,
+  stable: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let mut tags = subquery.image(&package).await?;
+  if stable {
+    tags = image::stable_image_tags(&tags)
+      .into_iter()
+      .map(|(_, tag)| tag.clone())
+      .collect();
+  }
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&tags, output);
+  }
+  tags.iter().for_each(|tag| println!("{}", tag));
+  Ok(())
+}
@@ -1,23 +1,38 @@
-use crate::command::types::LogsCommand;
+use crate::command::types::{LogsCommand, Output};
 use crate::Subquery;
 
-pub async fn handle_logs(subquery: &Subquery, command: LogsCommand) -> color_eyre::Result<()> {
+pub async fn handle_logs(
+  subquery: &Subquery,
+  command: LogsCommand,
+  output: &Output,
+) -> color_eyre::Result<()> {
   let key = format!("{}/{}", command.org, command.key);
   let mut viewed = vec![];
   loop {
     let log = subquery
       .search_logs(&key, command.stage, &command.level, command.keyword.clone())
       .await?;
+    let mut records = vec![];
     for ret in log.result {
       let ts = ret.timestamp.timestamp_millis();
       if viewed.contains(&ts) {
         continue;
       }
       viewed.push(ts);
-      println!(
-        "[{}] [{}] [{}] {} ",
-        ret.level, ret.timestamp, ret.category, ret.message
-      );
+      records.push(ret);
+    }
+    if crate::command::output::is_structured(output) {
+      // in rolling mode, each query prints the new records as a batch
+      if !records.is_empty() {
+        crate::command::output::output_structured(&records, output)?;
+      }
+    } else {
+      for ret in records {
+        println!(
+          "[{}] [{}] [{}] {} ",
+          ret.level, ret.timestamp, ret.category, ret.message
+        );
+      }
     }
     if !command.rolling {
       break;
@@ -1,8 +1,13 @@
-use crate::command::types::{OutputFormat, ProjectOpt};
+use crate::command::output::ActionResult;
+use crate::command::types::{Output, OutputFormat, ProjectOpt, TableOpt};
 use crate::subquery::{ApiVersion, Project};
-use crate::Subquery;
+use crate::{Subquery, SubqueryError};
 
-pub async fn handle_project(subquery: &Subquery, opt: ProjectOpt) -> color_eyre::Result<()> {
+pub async fn handle_project(
+  subquery: &Subquery,
+  opt: ProjectOpt,
+  output: &Output,
+) -> color_eyre::Result<()> {
   match opt {
     ProjectOpt::Create {
       org,
@@ -32,7 +37,7 @@ pub async fn handle_project(subquery: &Subquery, opt: ProjectOpt) -> color_eyre:
         query_url: None,
         deployment: None,
       };
-      handle_create(subquery, project, check).await
+      handle_create(subquery, project, check, output).await
     }
     ProjectOpt::Update {
       org,
@@ -60,14 +65,34 @@ pub async fn handle_project(subquery: &Subquery, opt: ProjectOpt) -> color_eyre:
         query_url: None,
         deployment: None,
       };
-      handle_update(subquery, project).await
+      handle_update(subquery, project, output).await
+    }
+    ProjectOpt::Delete { org, key } => {
+      handle_delete(subquery, format!("{}/{}", org, key), output).await
+    }
+    ProjectOpt::List { org, table } => handle_list(subquery, org, output, table).await,
+    ProjectOpt::Show {
+      org,
+      key,
+      with_deployments,
+    } => {
+      handle_show(
+        subquery,
+        format!("{}/{}", org, key),
+        with_deployments,
+        output,
+      )
+      .await
     }
-    ProjectOpt::Delete { org, key } => handle_delete(subquery, format!("{}/{}", org, key)).await,
-    ProjectOpt::List { org, output } => handle_list(subquery, org, output).await,
   }
 }
 
-async fn handle_delete(subquery: &Subquery, key: impl AsRef<str>) -> color_eyre::Result<()> {
+async fn handle_delete(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let result = ActionResult::new("delete_project", key.as_ref(), None);
   let question = requestty::Question::expand("delete")
     .message("Are you sure delete this project?")
     .choices(vec![('y', "Yes"), ('n', "No")])
@@ -77,10 +102,10 @@ async fn handle_delete(subquery: &Subquery, key: impl AsRef<str>) -> color_eyre:
   let answer = requestty::prompt_one(question)?;
   if let Some(v) = answer.as_expand_item() {
     if v.key != 'y' {
-      return Ok(());
+      return crate::command::output::output_action(result.unchanged(), output);
     }
     let _response = subquery.delete_project(key.as_ref()).await?;
-    println!("Success");
+    return crate::command::output::output_action(result, output);
   }
   Ok(())
 }
@@ -89,6 +114,7 @@ async fn handle_create(
   subquery: &Subquery,
   project: Project,
   check: bool,
+  output: &Output,
 ) -> color_eyre::Result<()> {
   if check {
     let projects = subquery
@@ -96,29 +122,70 @@ async fn handle_create(
       .await?;
     let this_key_project = projects.iter().find(|&item| item.key == project.key);
     if this_key_project.is_some() {
-      println!("{}", project.key);
-      return Ok(());
+      let result = ActionResult::new("create_project", &project.key, None).unchanged();
+      return output_created(result, output);
     }
   }
   let response = subquery.create_project(project).await?;
-  println!("{}", response.key);
+  output_created(
+    ActionResult::new("create_project", response.key, None),
+    output,
+  )
+}
+
+/// Raw output of create is the project key
+fn output_created(result: ActionResult, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&result, output);
+  }
+  println!("{}", result.project_key);
   Ok(())
 }
 
-async fn handle_update(subquery: &Subquery, project: Project) -> color_eyre::Result<()> {
+async fn handle_update(
+  subquery: &Subquery,
+  project: Project,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let result = ActionResult::new("update_project", &project.key, None);
   let _response = subquery.update_project(project).await?;
-  println!("Success");
-  Ok(())
+  crate::command::output::output_action(result, output)
+}
+
+async fn handle_show(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  with_deployments: bool,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let project = subquery
+    .project(key)
+    .await?
+    .ok_or_else(|| SubqueryError::Custom(format!("The project {} not found", key)))?;
+  let deployments = if with_deployments {
+    Some(subquery.deployments(key).await?)
+  } else {
+    None
+  };
+  crate::command::output::output_project_detail(project, deployments, output)
 }
 
 async fn handle_list(
   subquery: &Subquery,
   org: String,
-  format: OutputFormat,
+  output: &Output,
+  table: TableOpt,
 ) -> color_eyre::Result<()> {
   let projects = subquery.projects(org).await?;
-  match format {
-    OutputFormat::Raw | OutputFormat::Table => {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&projects, output);
+  }
+  match output.format {
+    OutputFormat::Table => {
+      crate::command::output::output_project_table(projects, &table)?;
+    }
+    _ => {
       projects.iter().for_each(|project| {
         let key_name = project.key.split('/').last();
         let project_name = project.name.clone().unwrap_or_default();
@@ -129,10 +196,6 @@ async fn handle_list(
         }
       });
     }
-    OutputFormat::Json => {
-      let json = serde_json::to_string_pretty(&projects)?;
-      println!("{}", json);
-    }
   }
   Ok(())
 }
@@ -0,0 +1,64 @@
+use crate::command::types::{Output, RepoOpt};
+use crate::subquery::{Deployment, DeploymentType};
+use crate::Subquery;
+
+pub async fn handle_repo(
+  subquery: &Subquery,
+  opt: RepoOpt,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  match opt {
+    RepoOpt::Branches { org, key } => {
+      handle_branches(subquery, format!("{}/{}", org, key), output).await
+    }
+    RepoOpt::Commits { org, key, branch } => {
+      handle_commits(subquery, format!("{}/{}", org, key), branch, output).await
+    }
+  }
+}
+
+async fn handle_branches(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let branches = subquery.branches(key).await?;
+  let deployments = subquery.deployments(key).await?;
+  let branches = branches
+    .into_iter()
+    .map(|branch| {
+      let deployed = deployed_types(&deployments, &branch.commit.sha);
+      (branch, deployed)
+    })
+    .collect();
+  crate::command::output::output_branches(branches, output)
+}
+
+async fn handle_commits(
+  subquery: &Subquery,
+  key: impl AsRef<str>,
+  branch: impl AsRef<str>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let key = key.as_ref();
+  let commits = subquery.commit(key, branch).await?;
+  let deployments = subquery.deployments(key).await?;
+  let commits = commits
+    .into_iter()
+    .map(|commit| {
+      let deployed = deployed_types(&deployments, &commit.sha);
+      (commit, deployed)
+    })
+    .collect();
+  crate::command::output::output_commits(commits, output)
+}
+
+/// Which deployments are deployed from this commit
+fn deployed_types(deployments: &[Deployment], sha: &str) -> Vec<DeploymentType> {
+  deployments
+    .iter()
+    .filter(|item| item.version == sha)
+    .map(|item| item.type_.clone())
+    .collect()
+}
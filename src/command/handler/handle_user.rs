@@ -2,18 +2,25 @@ use term_table::row::Row;
 use term_table::table_cell::{Alignment, TableCell};
 use term_table::{Table, TableStyle};
 
-use crate::command::types::UserOpt;
+use crate::command::types::{Output, UserOpt};
 use crate::Subquery;
 
-pub async fn handle_user(subquery: &Subquery, opt: UserOpt) -> color_eyre::Result<()> {
+pub async fn handle_user(
+  subquery: &Subquery,
+  opt: UserOpt,
+  output: &Output,
+) -> color_eyre::Result<()> {
   match opt {
-    UserOpt::Info => handle_user_info(subquery).await,
-    UserOpt::Orgs => handle_orgs(subquery).await,
+    UserOpt::Info => handle_user_info(subquery, output).await,
+    UserOpt::Orgs => handle_orgs(subquery, output).await,
   }
 }
 
-async fn handle_user_info(subquery: &Subquery) -> color_eyre::Result<()> {
+async fn handle_user_info(subquery: &Subquery, output: &Output) -> color_eyre::Result<()> {
   let user = subquery.user().await?;
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&user, output);
+  }
   let mut table = Table::new();
   table.max_column_width = 40;
   table.separate_rows = false;
@@ -34,8 +41,11 @@ async fn handle_user_info(subquery: &Subquery) -> color_eyre::Result<()> {
   Ok(())
 }
 
-async fn handle_orgs(subquery: &Subquery) -> color_eyre::Result<()> {
+async fn handle_orgs(subquery: &Subquery, output: &Output) -> color_eyre::Result<()> {
   let user = subquery.user().await?;
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&user.accounts, output);
+  }
   for account in &user.accounts {
     println!("{}", account.key)
   }
@@ -1,9 +1,19 @@
+pub use self::handle_apply::*;
 pub use self::handle_deployment::*;
+pub use self::handle_export::*;
+pub use self::handle_history::*;
+pub use self::handle_images::*;
 pub use self::handle_logs::*;
 pub use self::handle_project::*;
+pub use self::handle_repo::*;
 pub use self::handle_user::*;
 
+mod handle_apply;
 mod handle_deployment;
+mod handle_export;
+mod handle_history;
+mod handle_images;
 mod handle_logs;
 mod handle_project;
+mod handle_repo;
 mod handle_user;
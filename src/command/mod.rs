@@ -1,4 +1,6 @@
 pub mod handler;
+pub mod query;
+pub mod template;
 pub mod types;
 
 mod output;
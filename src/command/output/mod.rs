@@ -1,5 +1,23 @@
+pub use self::output_action::*;
+pub use self::output_apply::*;
 pub use self::output_deployment::*;
+pub use self::output_diff::*;
+pub use self::output_outdated::*;
+pub use self::output_plan::*;
 pub use self::output_project::*;
+pub use self::output_repo::*;
+pub use self::output_structured::*;
+pub use self::output_table::*;
+pub use self::output_upgrade::*;
 
+mod output_action;
+mod output_apply;
 mod output_deployment;
+mod output_diff;
+mod output_outdated;
+mod output_plan;
 mod output_project;
+mod output_repo;
+mod output_structured;
+mod output_table;
+mod output_upgrade;
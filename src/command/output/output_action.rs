@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::command::types::Output;
+
+/// Result of a command which changes a project or deployment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionResult {
+  /// Api action, e.g. update_project, redeploy, promote
+  pub action: String,
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  /// Deployment id
+  pub id: Option<u64>,
+  /// False if nothing was changed, e.g. the project already exists or the change is aborted
+  pub changed: bool,
+}
+
+impl ActionResult {
+  pub fn new(action: impl AsRef<str>, project_key: impl AsRef<str>, id: Option<u64>) -> Self {
+    Self {
+      action: action.as_ref().to_string(),
+      project_key: project_key.as_ref().to_string(),
+      id,
+      changed: true,
+    }
+  }
+
+  pub fn unchanged(mut self) -> Self {
+    self.changed = false;
+    self
+  }
+}
+
+/// Raw output only prints `Success` when something was changed
+pub fn output_action(result: ActionResult, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&result, output);
+  }
+  if result.changed {
+    println!("Success");
+  }
+  Ok(())
+}
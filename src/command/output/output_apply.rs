@@ -0,0 +1,126 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::Output;
+
+/// What apply did to a resource of project
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApplyResult {
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  /// project, stage or primary
+  pub resource: String,
+  pub action: ApplyAction,
+  /// The changed fields or the error message
+  pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ApplyAction {
+  #[serde(rename = "created")]
+  Created,
+  #[serde(rename = "updated")]
+  Updated,
+  #[serde(rename = "deployed")]
+  Deployed,
+  #[serde(rename = "redeployed")]
+  Redeployed,
+  #[serde(rename = "unchanged")]
+  Unchanged,
+  #[serde(rename = "planned")]
+  Planned,
+  #[serde(rename = "failed")]
+  Failed,
+}
+
+/// A field of live state different from the spec
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpecDrift {
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  /// project, stage or primary
+  pub resource: String,
+  pub field: String,
+  pub desired: String,
+  pub live: String,
+}
+
+pub fn output_apply(results: Vec<ApplyResult>, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&results, output);
+  }
+  let mut table = Table::new();
+  table.max_column_width = 60;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Project key".bold()),
+    TableCell::new("Resource".bold()),
+    TableCell::new("Action".bold()),
+    TableCell::new("Message".bold()),
+  ]));
+  for result in &results {
+    table.add_row(Row::new(vec![
+      TableCell::new(&result.project_key),
+      TableCell::new(&result.resource),
+      TableCell::new(match result.action {
+        ApplyAction::Created => "Created".bold().green(),
+        ApplyAction::Updated => "Updated".bold().green(),
+        ApplyAction::Deployed => "Deployed".bold().green(),
+        ApplyAction::Redeployed => "Redeployed".bold().green(),
+        ApplyAction::Unchanged => "Unchanged".normal(),
+        ApplyAction::Planned => "Planned".bold().cyan(),
+        ApplyAction::Failed => "Failed".bold().red(),
+      }),
+      TableCell::new(result.message.clone().unwrap_or_default()),
+    ]));
+  }
+  println!("{}", table.render());
+  let count = |action: ApplyAction| results.iter().filter(|v| v.action == action).count();
+  println!(
+    "created: {} updated: {} deployed: {} redeployed: {} unchanged: {} planned: {} failed: {}",
+    count(ApplyAction::Created),
+    count(ApplyAction::Updated),
+    count(ApplyAction::Deployed),
+    count(ApplyAction::Redeployed),
+    count(ApplyAction::Unchanged),
+    count(ApplyAction::Planned),
+    count(ApplyAction::Failed),
+  );
+  Ok(())
+}
+
+pub fn output_spec_drift(drifts: &[SpecDrift], output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(drifts, output);
+  }
+  if drifts.is_empty() {
+    println!("No drift, the live state matches the spec");
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 50;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Project key".bold()),
+    TableCell::new("Resource".bold()),
+    TableCell::new("Field".bold()),
+    TableCell::new("Desired".bold()),
+    TableCell::new("Live".bold()),
+  ]));
+  for drift in drifts {
+    table.add_row(Row::new(vec![
+      TableCell::new(&drift.project_key),
+      TableCell::new(&drift.resource),
+      TableCell::new(drift.field.yellow()),
+      TableCell::new(drift.desired.green()),
+      TableCell::new(drift.live.red()),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
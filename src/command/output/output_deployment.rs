@@ -3,23 +3,55 @@ use term_table::row::Row;
 use term_table::table_cell::{Alignment, TableCell};
 use term_table::{Table, TableStyle};
 
-use crate::command::types::OutputFormat;
+use crate::command::output::{output_rows, Column};
+use crate::command::types::{Output, OutputFormat, TableOpt};
 use crate::subquery::{Deployment, DeploymentStatus, DeploymentType};
 
-pub fn output_deployment(
-  deployments: Vec<Deployment>,
-  format: OutputFormat,
-) -> color_eyre::Result<()> {
-  match format {
-    OutputFormat::Raw => output_raw(deployments),
-    OutputFormat::Json => output_json(deployments),
-    OutputFormat::Table => output_table(deployments),
+pub fn output_deployment(deployments: Vec<Deployment>, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&deployments, output);
+  }
+  match output.format {
+    OutputFormat::Table => output_deployment_table(deployments, &TableOpt::default()),
+    _ => output_raw(deployments),
   }
 }
 
-fn output_json(deployments: Vec<Deployment>) -> color_eyre::Result<()> {
-  println!("{}", serde_json::to_string_pretty(&deployments)?);
-  Ok(())
+/// Output deployments as table, one row per deployment
+pub fn output_deployment_table(
+  deployments: Vec<Deployment>,
+  opt: &TableOpt,
+) -> color_eyre::Result<()> {
+  let columns: Vec<Column<Deployment>> = vec![
+    Column::new("id", |v| v.id.to_string()),
+    Column::new("type", |v| v.type_.as_ref().to_string()),
+    Column::new("status", |v| format!("{:?}", v.status)),
+    Column::new("commit", |v| v.version.clone()),
+    Column::new("indexer-image", |v| v.indexer_image.clone()),
+    Column::new("query-image", |v| v.query_image.clone()),
+    Column::new("created-at", |v| v.created_at.to_rfc3339()),
+    Column::new("updated-at", |v| {
+      v.updated_at.map(|v| v.to_rfc3339()).unwrap_or_default()
+    }),
+    Column::new("project-key", |v| v.project_key.clone()),
+    Column::new("cluster", |v| v.cluster.clone()),
+    Column::new("sub-folder", |v| v.sub_folder.clone().unwrap_or_default()),
+    Column::new("endpoint", |v| v.endpoint.clone().unwrap_or_default()),
+    Column::new("dict-endpoint", |v| {
+      v.dict_endpoint.clone().unwrap_or_default()
+    }),
+    Column::new("query-url", |v| v.query_url.clone()),
+  ];
+  let defaults = [
+    "id",
+    "type",
+    "status",
+    "commit",
+    "indexer-image",
+    "query-image",
+    "created-at",
+  ];
+  output_rows(&deployments, &columns, &defaults, opt)
 }
 
 fn output_raw(deployments: Vec<Deployment>) -> color_eyre::Result<()> {
@@ -44,10 +76,6 @@ fn output_raw(deployments: Vec<Deployment>) -> color_eyre::Result<()> {
   Ok(())
 }
 
-fn output_table(deployments: Vec<Deployment>) -> color_eyre::Result<()> {
-  output_raw(deployments)
-}
-
 fn _output_raw_deployment(deployment: &Deployment) -> color_eyre::Result<()> {
   let mut table = Table::new();
   table.max_column_width = 40;
@@ -0,0 +1,171 @@
+use colored::Colorize;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::{Output, OutputFormat};
+use crate::subquery::{Deployment, SyncStatus};
+
+/// Output the difference of primary and stage deployment side by side
+pub fn output_deployment_diff(
+  primary: (Option<&Deployment>, Option<&SyncStatus>),
+  stage: (Option<&Deployment>, Option<&SyncStatus>),
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let primary_fields = diff_fields(primary.0, primary.1);
+  let stage_fields = diff_fields(stage.0, stage.1);
+  let fields = primary_fields
+    .into_iter()
+    .zip(stage_fields)
+    .map(|((name, primary), (_, stage))| (name, primary, stage))
+    .collect::<Vec<(&str, String, String)>>();
+  if crate::command::output::is_structured(output) {
+    let fields = fields
+      .iter()
+      .map(|(name, primary, stage)| {
+        serde_json::json!({
+          "name": name,
+          "primary": primary,
+          "stage": stage,
+          "same": primary == stage,
+        })
+      })
+      .collect::<Vec<serde_json::Value>>();
+    let diff = serde_json::json!({
+      "primary": primary.0.map(|v| v.id),
+      "stage": stage.0.map(|v| v.id),
+      "fields": fields,
+    });
+    return match output.format {
+      // tabular formats only have the fields
+      OutputFormat::Csv | OutputFormat::Markdown => {
+        crate::command::output::output_structured(&fields, output)
+      }
+      _ => crate::command::output::output_structured(&diff, output),
+    };
+  }
+  let mut table = Table::new();
+  table.max_column_width = 50;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Name".bold()),
+    TableCell::new("Primary".bold().blue()),
+    TableCell::new("Stage".bold().blue()),
+  ]));
+  table.add_row(Row::new(vec![
+    TableCell::new("Id".bold()),
+    TableCell::new(primary.0.map(|v| v.id.to_string()).unwrap_or_default()),
+    TableCell::new(stage.0.map(|v| v.id.to_string()).unwrap_or_default()),
+  ]));
+  for (name, primary, stage) in fields {
+    if primary == stage {
+      table.add_row(Row::new(vec![
+        TableCell::new(name.bold()),
+        TableCell::new(primary),
+        TableCell::new(stage),
+      ]));
+    } else {
+      table.add_row(Row::new(vec![
+        TableCell::new(name.bold().yellow()),
+        TableCell::new(primary.red()),
+        TableCell::new(stage.green()),
+      ]));
+    }
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+fn diff_fields(
+  deployment: Option<&Deployment>,
+  status: Option<&SyncStatus>,
+) -> Vec<(&'static str, String)> {
+  let metadata = deployment.and_then(|v| v.metadata.as_ref());
+  vec![
+    (
+      "Status",
+      deployment
+        .map(|v| format!("{:?}", v.status))
+        .unwrap_or_default(),
+    ),
+    (
+      "Commit",
+      deployment.map(|v| v.version.clone()).unwrap_or_default(),
+    ),
+    (
+      "Indexer image",
+      deployment
+        .map(|v| v.indexer_image.clone())
+        .unwrap_or_default(),
+    ),
+    (
+      "Query image",
+      deployment
+        .map(|v| v.query_image.clone())
+        .unwrap_or_default(),
+    ),
+    (
+      "Endpoint",
+      deployment
+        .and_then(|v| v.endpoint.clone())
+        .unwrap_or_default(),
+    ),
+    (
+      "Dict endpoint",
+      deployment
+        .and_then(|v| v.dict_endpoint.clone())
+        .unwrap_or_default(),
+    ),
+    (
+      "Sub folder",
+      deployment
+        .and_then(|v| v.sub_folder.clone())
+        .unwrap_or_default(),
+    ),
+    (
+      "Batch size",
+      deployment
+        .and_then(|v| v.batch_size())
+        .map(|v| v.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Enable timestamp",
+      metadata
+        .map(|v| v.enable_timestamp.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Created at",
+      deployment
+        .map(|v| v.created_at.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Updated at",
+      deployment
+        .and_then(|v| v.updated_at)
+        .map(|v| v.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Processing block",
+      status
+        .map(|v| v.processing_block.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Target block",
+      status
+        .map(|v| v.target_block.to_string())
+        .unwrap_or_default(),
+    ),
+    (
+      "Progress",
+      status
+        .map(|v| format!("{:.2}%", v.percent()))
+        .unwrap_or_default(),
+    ),
+  ]
+}
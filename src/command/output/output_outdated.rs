@@ -0,0 +1,91 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::Output;
+use crate::subquery::DeploymentType;
+
+/// Image versions of a deployment compared with the newest stable image tags
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutdatedDeployment {
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  pub id: u64,
+  #[serde(rename = "type")]
+  pub type_: DeploymentType,
+  #[serde(rename = "indexerImageVersion")]
+  pub indexer_image_version: String,
+  /// How many stable versions behind, none if the version is unknown
+  #[serde(rename = "indexerBehind")]
+  pub indexer_behind: Option<usize>,
+  #[serde(rename = "latestIndexerImageVersion")]
+  pub latest_indexer_image_version: String,
+  #[serde(rename = "queryImageVersion")]
+  pub query_image_version: String,
+  /// How many stable versions behind, none if the version is unknown
+  #[serde(rename = "queryBehind")]
+  pub query_behind: Option<usize>,
+  #[serde(rename = "latestQueryImageVersion")]
+  pub latest_query_image_version: String,
+}
+
+impl OutdatedDeployment {
+  pub fn is_outdated(&self) -> bool {
+    self.indexer_behind.unwrap_or_default() > 0 || self.query_behind.unwrap_or_default() > 0
+  }
+}
+
+pub fn output_outdated(
+  deployments: Vec<OutdatedDeployment>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&deployments, output);
+  }
+  if deployments.is_empty() {
+    println!("All deployments are up to date");
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 40;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Project key".bold()),
+    TableCell::new("Id".bold()),
+    TableCell::new("Type".bold()),
+    TableCell::new("Indexer image".bold()),
+    TableCell::new("Behind".bold()),
+    TableCell::new("Query image".bold()),
+    TableCell::new("Behind".bold()),
+  ]));
+  for deployment in deployments {
+    table.add_row(Row::new(vec![
+      TableCell::new(&deployment.project_key),
+      TableCell::new(deployment.id),
+      TableCell::new(format!("{:?}", deployment.type_)),
+      TableCell::new(format!(
+        "{} -> {}",
+        deployment.indexer_image_version, deployment.latest_indexer_image_version
+      )),
+      TableCell::new(behind(deployment.indexer_behind)),
+      TableCell::new(format!(
+        "{} -> {}",
+        deployment.query_image_version, deployment.latest_query_image_version
+      )),
+      TableCell::new(behind(deployment.query_behind)),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+fn behind(versions: Option<usize>) -> colored::ColoredString {
+  match versions {
+    Some(0) => "0".green(),
+    Some(v) => v.to_string().yellow(),
+    None => "unknown".red(),
+  }
+}
@@ -0,0 +1,137 @@
+use colored::Colorize;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::{Output, OutputFormat};
+use crate::subquery::{CreateDeployRequest, Deployment};
+
+/// Output what a deploy would do, the request and the changes compared with current deployment
+pub fn output_deploy_plan(
+  action: impl AsRef<str>,
+  deployment: &CreateDeployRequest,
+  current: Option<&Deployment>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let changes = deploy_changes(deployment, current);
+  if crate::command::output::is_structured(output) {
+    let changes = changes
+      .iter()
+      .map(|(name, current, new)| {
+        serde_json::json!({
+          "name": name,
+          "current": current,
+          "new": new,
+          "changed": current != new,
+        })
+      })
+      .collect::<Vec<serde_json::Value>>();
+    let plan = serde_json::json!({
+      "action": action.as_ref(),
+      "deploymentId": current.map(|v| v.id),
+      "request": deployment,
+      "changes": changes,
+    });
+    return match output.format {
+      // tabular formats only have the changes
+      OutputFormat::Csv | OutputFormat::Markdown => {
+        crate::command::output::output_structured(&changes, output)
+      }
+      _ => crate::command::output::output_structured(&plan, output),
+    };
+  }
+  println!("{} {}", "Action".bold().blue(), action.as_ref().bold());
+  println!();
+  println!("{}", "Request".bold().blue());
+  println!("{}", serde_json::to_string_pretty(deployment)?);
+  println!();
+  println!("{}", "Changes".bold().blue());
+  let mut table = Table::new();
+  table.max_column_width = 50;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Name".bold()),
+    TableCell::new("Current".bold()),
+    TableCell::new("New".bold()),
+  ]));
+  for (name, current, new) in changes {
+    let changed = current != new;
+    table.add_row(Row::new(vec![
+      TableCell::new(if changed {
+        name.yellow()
+      } else {
+        name.normal()
+      }),
+      TableCell::new(current),
+      TableCell::new(if changed { new.yellow() } else { new.normal() }),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+/// The settings of current deployment and deploy request, (name, current, new)
+pub fn deploy_changes(
+  deployment: &CreateDeployRequest,
+  current: Option<&Deployment>,
+) -> Vec<(&'static str, String, String)> {
+  let settings = &deployment.advanced_settings.subql_node;
+  vec![
+    (
+      "Commit",
+      current.map(|v| v.version.clone()).unwrap_or_default(),
+      deployment.commit.clone().unwrap_or_default(),
+    ),
+    (
+      "Indexer image version",
+      current
+        .and_then(|v| v.indexer_image_version())
+        .unwrap_or_default()
+        .to_string(),
+      deployment.indexer_image_version.clone().unwrap_or_default(),
+    ),
+    (
+      "Query image version",
+      current
+        .and_then(|v| v.query_image_version())
+        .unwrap_or_default()
+        .to_string(),
+      deployment.query_image_version.clone().unwrap_or_default(),
+    ),
+    (
+      "Endpoint",
+      current.and_then(|v| v.endpoint.clone()).unwrap_or_default(),
+      deployment.endpoint.clone().unwrap_or_default(),
+    ),
+    (
+      "Dict endpoint",
+      current
+        .and_then(|v| v.dict_endpoint.clone())
+        .unwrap_or_default(),
+      deployment.dict_endpoint.clone().unwrap_or_default(),
+    ),
+    (
+      "Sub folder",
+      current
+        .and_then(|v| v.sub_folder.clone())
+        .unwrap_or_default(),
+      deployment.sub_folder.clone().unwrap_or_default(),
+    ),
+    (
+      "Batch size",
+      current
+        .and_then(|v| v.batch_size())
+        .map(|v| v.to_string())
+        .unwrap_or_default(),
+      settings.batch_size.to_string(),
+    ),
+    (
+      "Subscription",
+      current
+        .map(|v| v.subscription().unwrap_or(false).to_string())
+        .unwrap_or_default(),
+      settings.subscription.to_string(),
+    ),
+  ]
+}
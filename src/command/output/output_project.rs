@@ -1,17 +1,84 @@
 use colored::Colorize;
+use serde::Serialize;
 use term_table::row::Row;
 use term_table::table_cell::{Alignment, TableCell};
 use term_table::{Table, TableStyle};
 
-use crate::command::types::OutputFormat;
-use crate::subquery::Project;
+use crate::command::output::{output_rows, Column};
+use crate::command::types::{Output, OutputFormat, TableOpt};
+use crate::subquery::{Deployment, Project};
 
-pub fn output_project(project: Project, format: OutputFormat) -> color_eyre::Result<()> {
-  match format {
-    OutputFormat::Raw => output_raw(project),
-    OutputFormat::Json => output_json(project),
-    OutputFormat::Table => output_table(project),
+/// Project with all deployments of it
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectDetail {
+  #[serde(flatten)]
+  pub project: Project,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deployments: Option<Vec<Deployment>>,
+}
+
+pub fn output_project(project: Project, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&project, output);
+  }
+  match output.format {
+    OutputFormat::Table => output_project_table(vec![project], &TableOpt::default()),
+    _ => output_raw(project),
+  }
+}
+
+/// Output a project, followed by all deployments if given, otherwise the deployment of project
+pub fn output_project_detail(
+  mut project: Project,
+  deployments: Option<Vec<Deployment>>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    let detail = ProjectDetail {
+      project,
+      deployments,
+    };
+    return crate::command::output::output_structured(&detail, output);
+  }
+  let deployments = match deployments {
+    Some(v) => v,
+    None => project.deployment.take().into_iter().collect(),
+  };
+  match output.format {
+    OutputFormat::Table => {
+      output_project_table(vec![project], &TableOpt::default())?;
+      println!();
+    }
+    _ => output_raw(project)?,
+  }
+  if deployments.is_empty() {
+    return Ok(());
   }
+  crate::command::output::output_deployment(deployments, output)
+}
+
+/// Output projects as table, one row per project
+pub fn output_project_table(projects: Vec<Project>, opt: &TableOpt) -> color_eyre::Result<()> {
+  let columns: Vec<Column<Project>> = vec![
+    Column::new("key", |v| v.key.clone()),
+    Column::new("name", |v| v.name.clone().unwrap_or_default()),
+    Column::new("network", |v| v.network.clone().unwrap_or_default()),
+    Column::new("deployed", |v| v.deployed.unwrap_or_default().to_string()),
+    Column::new("hidden", |v| v.hide.unwrap_or_default().to_string()),
+    Column::new("account", |v| v.account.clone().unwrap_or_default()),
+    Column::new("subtitle", |v| v.subtitle.clone().unwrap_or_default()),
+    Column::new("description", |v| v.description.clone().unwrap_or_default()),
+    Column::new("repo", |v| v.git_repository.clone().unwrap_or_default()),
+    Column::new("query-url", |v| v.query_url.clone().unwrap_or_default()),
+    Column::new("created-at", |v| {
+      v.created_at.map(|v| v.to_rfc3339()).unwrap_or_default()
+    }),
+    Column::new("updated-at", |v| {
+      v.updated_at.map(|v| v.to_rfc3339()).unwrap_or_default()
+    }),
+  ];
+  let defaults = ["key", "name", "network", "deployed", "hidden"];
+  output_rows(&projects, &columns, &defaults, opt)
 }
 
 fn output_raw(project: Project) -> color_eyre::Result<()> {
@@ -108,16 +175,7 @@ fn output_raw(project: Project) -> color_eyre::Result<()> {
   println!();
   if let Some(deployment) = project.deployment {
     let deployments = vec![deployment];
-    crate::command::output::output_deployment(deployments, OutputFormat::Raw)?;
+    crate::command::output::output_deployment(deployments, &Output::default())?;
   }
   Ok(())
 }
-
-fn output_json(project: Project) -> color_eyre::Result<()> {
-  println!("{}", serde_json::to_string_pretty(&project)?);
-  Ok(())
-}
-
-fn output_table(project: Project) -> color_eyre::Result<()> {
-  output_raw(project)
-}
@@ -0,0 +1,175 @@
+use colored::Colorize;
+use serde::Serialize;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::{Output, OutputFormat};
+use crate::subquery::{Branch, Commit, DeploymentType};
+
+#[derive(Serialize)]
+struct Deployed<'a, T: Serialize> {
+  #[serde(flatten)]
+  item: &'a T,
+  deployed: &'a [DeploymentType],
+}
+
+/// Output branches, with the deployment types which deployed from the head commit of branch
+pub fn output_branches(
+  branches: Vec<(Branch, Vec<DeploymentType>)>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    let branches = branches
+      .iter()
+      .map(|(item, deployed)| Deployed { item, deployed })
+      .collect::<Vec<_>>();
+    crate::command::output::output_structured(&branches, output)?;
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 60;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Name".bold()),
+    TableCell::new("Protected".bold()),
+    TableCell::new("Commit".bold()),
+    TableCell::new("Deployed".bold()),
+  ]));
+  for (branch, deployed) in branches {
+    table.add_row(Row::new(vec![
+      TableCell::new(branch.name),
+      TableCell::new(branch.protected),
+      TableCell::new(branch.commit.sha),
+      TableCell::new(deployed_text(&deployed)),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+/// Output commits, with the deployment types which deployed from the commit
+pub fn output_commits(
+  commits: Vec<(Commit, Vec<DeploymentType>)>,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    let commits = commits
+      .iter()
+      .map(|(item, deployed)| Deployed { item, deployed })
+      .collect::<Vec<_>>();
+    crate::command::output::output_structured(&commits, output)?;
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 60;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Commit".bold()),
+    TableCell::new("Author".bold()),
+    TableCell::new("Time".bold()),
+    TableCell::new("Message".bold()),
+    TableCell::new("Deployed".bold()),
+  ]));
+  for (commit, deployed) in commits {
+    table.add_row(Row::new(vec![
+      TableCell::new(commit.sha.chars().take(7).collect::<String>()),
+      TableCell::new(commit.author.name),
+      TableCell::new(commit.time.format("%Y-%m-%d %H:%M:%S")),
+      TableCell::new(commit.message.lines().next().unwrap_or_default()),
+      TableCell::new(deployed_text(&deployed)),
+    ]));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+fn deployed_text(deployed: &[DeploymentType]) -> colored::ColoredString {
+  deployed
+    .iter()
+    .map(|v| v.as_ref())
+    .collect::<Vec<&str>>()
+    .join(", ")
+    .bold()
+    .green()
+}
+
+/// Commits between the deployed commit and branch head
+#[derive(Clone, Debug, Serialize)]
+pub struct Changelog {
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  #[serde(rename = "type")]
+  pub type_: DeploymentType,
+  pub branch: String,
+  /// The deployed commit, none if not deployed yet
+  pub from: Option<String>,
+  /// The branch head, none if nothing to ship
+  pub to: Option<String>,
+  /// The commits would be shipped, the newest is first
+  pub commits: Vec<Commit>,
+}
+
+pub fn output_changelog(changelog: Changelog, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return match output.format {
+      // markdown is the release notes unless the output is filtered or formatted
+      OutputFormat::Markdown if !output.is_filtered() => output_changelog_markdown(changelog),
+      OutputFormat::Csv | OutputFormat::Markdown => {
+        crate::command::output::output_structured(&changelog.commits, output)
+      }
+      _ => crate::command::output::output_structured(&changelog, output),
+    };
+  }
+  if changelog.commits.is_empty() {
+    println!("Nothing to deploy, the deployed commit is the head of branch");
+    return Ok(());
+  }
+  let commits = changelog
+    .commits
+    .into_iter()
+    .map(|commit| (commit, vec![]))
+    .collect();
+  output_commits(commits, &Output::default())
+}
+
+pub fn output_changelog_markdown(changelog: Changelog) -> color_eyre::Result<()> {
+  let short = |sha: &str| sha.chars().take(7).collect::<String>();
+  println!(
+    "## {} ({})",
+    changelog.project_key,
+    changelog.type_.as_ref()
+  );
+  println!();
+  match (&changelog.from, &changelog.to) {
+    (Some(from), Some(to)) => println!(
+      "`{}`...`{}` on branch `{}`, {} commits",
+      short(from),
+      short(to),
+      changelog.branch,
+      changelog.commits.len()
+    ),
+    (None, Some(to)) => println!(
+      "First deploy `{}` on branch `{}`, {} commits",
+      short(to),
+      changelog.branch,
+      changelog.commits.len()
+    ),
+    (_, None) => {
+      println!("Nothing to deploy");
+      return Ok(());
+    }
+  }
+  println!();
+  for commit in &changelog.commits {
+    println!(
+      "- `{}` {} (@{})",
+      short(&commit.sha),
+      commit.message.lines().next().unwrap_or_default(),
+      commit.author.name
+    );
+  }
+  Ok(())
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+
+use crate::command::types::{Output, OutputFormat};
+
+/// Output serializable data as json, yaml, csv or markdown table. a list is one row per item and
+/// an object is one row, nested values are written as json in the cell. raw and table are not
+/// structured formats, the data is written as json.
+///
+/// If `--jq` is given, the data is filtered first. for json each result is written in a line and
+/// strings are written without quotes, other formats write the result or the list of results.
+///
+/// If `--format` is given, each item is rendered by the template instead of the output format.
+pub fn output_structured<T: Serialize + ?Sized>(
+  data: &T,
+  output: &Output,
+) -> color_eyre::Result<()> {
+  let value = serde_json::to_value(data)?;
+  if let Some(template) = &output.template {
+    let values = match &output.query {
+      Some(query) => query.apply(&value)?,
+      None => vec![value],
+    };
+    for value in values {
+      for line in template.render(&value)? {
+        println!("{}", line);
+      }
+    }
+    return Ok(());
+  }
+  let query = match &output.query {
+    Some(v) => v,
+    None => return write_structured(value, output.format),
+  };
+  let mut results = query.apply(&value)?;
+  match output.format {
+    OutputFormat::Json | OutputFormat::Raw | OutputFormat::Table => {
+      for result in results {
+        match result {
+          serde_json::Value::String(v) => println!("{}", v),
+          v => println!("{}", serde_json::to_string_pretty(&v)?),
+        }
+      }
+      Ok(())
+    }
+    _ if results.len() == 1 => write_structured(results.remove(0), output.format),
+    _ => write_structured(serde_json::Value::Array(results), output.format),
+  }
+}
+
+/// Whether the output is written by `output_structured`, raw and table are written as text unless
+/// a `--jq` query or `--format` template is given
+pub fn is_structured(output: &Output) -> bool {
+  !matches!(output.format, OutputFormat::Raw | OutputFormat::Table) || output.is_filtered()
+}
+
+fn write_structured(value: serde_json::Value, format: OutputFormat) -> color_eyre::Result<()> {
+  match format {
+    OutputFormat::Yaml => {
+      print!("{}", serde_yaml::to_string(&value)?);
+    }
+    OutputFormat::Csv => {
+      let (headers, rows) = structured_rows(value);
+      let mut writer = csv::Writer::from_writer(std::io::stdout());
+      writer.write_record(&headers)?;
+      for row in rows {
+        writer.write_record(&row)?;
+      }
+      writer.flush()?;
+    }
+    OutputFormat::Markdown => {
+      let (headers, rows) = structured_rows(value);
+      println!("{}", markdown_row(&headers));
+      println!(
+        "{}",
+        markdown_row(
+          &headers
+            .iter()
+            .map(|_| "---".to_string())
+            .collect::<Vec<_>>()
+        )
+      );
+      for row in rows {
+        println!("{}", markdown_row(&row));
+      }
+    }
+    OutputFormat::Json | OutputFormat::Raw | OutputFormat::Table => {
+      println!("{}", serde_json::to_string_pretty(&value)?);
+    }
+  }
+  Ok(())
+}
+
+/// Headers and rows of value, the headers are the keys in order of appearance
+fn structured_rows(value: serde_json::Value) -> (Vec<String>, Vec<Vec<String>>) {
+  let items = match value {
+    serde_json::Value::Array(v) => v,
+    serde_json::Value::Null => vec![],
+    v => vec![v],
+  };
+  let mut headers: Vec<String> = vec![];
+  for item in &items {
+    match item {
+      serde_json::Value::Object(map) => {
+        for key in map.keys() {
+          if !headers.contains(key) {
+            headers.push(key.clone());
+          }
+        }
+      }
+      _ => {
+        if !headers.iter().any(|v| v == "value") {
+          headers.push("value".to_string());
+        }
+      }
+    }
+  }
+  let rows = items
+    .iter()
+    .map(|item| {
+      headers
+        .iter()
+        .map(|header| match item {
+          serde_json::Value::Object(map) => map.get(header).map(cell).unwrap_or_default(),
+          v if header == "value" => cell(v),
+          _ => String::new(),
+        })
+        .collect()
+    })
+    .collect();
+  (headers, rows)
+}
+
+fn cell(value: &serde_json::Value) -> String {
+  match value {
+    serde_json::Value::Null => String::new(),
+    serde_json::Value::String(v) => v.clone(),
+    v => v.to_string(),
+  }
+}
+
+fn markdown_row(cells: &[String]) -> String {
+  let cells = cells
+    .iter()
+    .map(|v| v.replace('|', "\\|").replace('\n', "<br>"))
+    .collect::<Vec<String>>();
+  format!("| {} |", cells.join(" | "))
+}
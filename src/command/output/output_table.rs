@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+
+use colored::Colorize;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::TableOpt;
+use crate::error::SubqueryError;
+
+/// A column of table output, the name and how to get the cell of a row
+pub struct Column<T> {
+  pub name: &'static str,
+  pub value: fn(&T) -> String,
+}
+
+impl<T> Column<T> {
+  pub fn new(name: &'static str, value: fn(&T) -> String) -> Self {
+    Self { name, value }
+  }
+}
+
+/// Output one row per item, the columns are chosen by `--columns` or the defaults, and the rows
+/// are sorted by `--sort-by`
+pub fn output_rows<T>(
+  items: &[T],
+  columns: &[Column<T>],
+  defaults: &[&str],
+  opt: &TableOpt,
+) -> color_eyre::Result<()> {
+  let names = match &opt.columns {
+    Some(v) => v.iter().map(|v| v.trim()).collect::<Vec<&str>>(),
+    None => defaults.to_vec(),
+  };
+  let selected = names
+    .iter()
+    .map(|name| find_column(columns, name))
+    .collect::<color_eyre::Result<Vec<&Column<T>>>>()?;
+
+  let mut rows = items
+    .iter()
+    .map(|item| {
+      columns
+        .iter()
+        .map(|column| (column.name, (column.value)(item)))
+        .collect::<Vec<(&str, String)>>()
+    })
+    .collect::<Vec<Vec<(&str, String)>>>();
+  if let Some(sort_by) = &opt.sort_by {
+    let (name, descending) = match sort_by.strip_prefix('-') {
+      Some(v) => (v, true),
+      None => (sort_by.as_str(), false),
+    };
+    let column = find_column(columns, name)?;
+    let index = columns
+      .iter()
+      .position(|v| v.name == column.name)
+      .unwrap_or_default();
+    rows.sort_by(|a, b| {
+      let ordering = compare_cell(&a[index].1, &b[index].1);
+      if descending {
+        ordering.reverse()
+      } else {
+        ordering
+      }
+    });
+  }
+
+  let mut table = Table::new();
+  table.max_column_width = 60;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(
+    selected
+      .iter()
+      .map(|column| TableCell::new(header(column.name).bold()))
+      .collect::<Vec<TableCell>>(),
+  ));
+  for row in rows {
+    table.add_row(Row::new(
+      selected
+        .iter()
+        .map(|column| {
+          let value = row
+            .iter()
+            .find(|(name, _)| *name == column.name)
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_default();
+          TableCell::new(value)
+        })
+        .collect::<Vec<TableCell>>(),
+    ));
+  }
+  println!("{}", table.render());
+  Ok(())
+}
+
+/// The column by name, `created_at` and `created-at` are the same
+fn find_column<'a, T>(columns: &'a [Column<T>], name: &str) -> color_eyre::Result<&'a Column<T>> {
+  let name = name.replace('_', "-").to_lowercase();
+  columns
+    .iter()
+    .find(|column| column.name == name)
+    .ok_or_else(|| unknown_column(columns, &name))
+}
+
+fn unknown_column<T>(columns: &[Column<T>], name: &str) -> color_eyre::Report {
+  let names = columns
+    .iter()
+    .map(|column| column.name)
+    .collect::<Vec<&str>>();
+  SubqueryError::Custom(format!(
+    "Unknown column {}, available columns: {}",
+    name,
+    names.join(", ")
+  ))
+  .into()
+}
+
+/// Numbers are compared by value, others by text
+fn compare_cell(a: &str, b: &str) -> Ordering {
+  match (a.parse::<f64>(), b.parse::<f64>()) {
+    (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    _ => a.cmp(b),
+  }
+}
+
+/// created-at -> Created at
+fn header(name: &str) -> String {
+  let name = name.replace('-', " ");
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_uppercase().chain(chars).collect(),
+    None => name,
+  }
+}
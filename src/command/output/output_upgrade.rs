@@ -0,0 +1,86 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::command::types::Output;
+
+/// Upgrade result of a project
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpgradeResult {
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  pub id: Option<u64>,
+  /// Current and new indexer image version, e.g. v0.25.3 -> v0.28.2
+  #[serde(rename = "indexerImageVersion")]
+  pub indexer_image_version: Option<String>,
+  /// Current and new query image version, e.g. v0.8.0 -> v0.12.0
+  #[serde(rename = "queryImageVersion")]
+  pub query_image_version: Option<String>,
+  pub status: UpgradeStatus,
+  pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UpgradeStatus {
+  #[serde(rename = "upgraded")]
+  Upgraded,
+  #[serde(rename = "planned")]
+  Planned,
+  #[serde(rename = "unchanged")]
+  Unchanged,
+  #[serde(rename = "skipped")]
+  Skipped,
+  #[serde(rename = "failed")]
+  Failed,
+}
+
+pub fn output_upgrade(results: Vec<UpgradeResult>, output: &Output) -> color_eyre::Result<()> {
+  if crate::command::output::is_structured(output) {
+    return crate::command::output::output_structured(&results, output);
+  }
+  if results.is_empty() {
+    println!("Not found any matched project");
+    return Ok(());
+  }
+  let mut table = Table::new();
+  table.max_column_width = 60;
+  table.separate_rows = false;
+  table.style = TableStyle::empty();
+  table.add_row(Row::new(vec![
+    TableCell::new("Project key".bold()),
+    TableCell::new("Id".bold()),
+    TableCell::new("Indexer image".bold()),
+    TableCell::new("Query image".bold()),
+    TableCell::new("Result".bold()),
+    TableCell::new("Message".bold()),
+  ]));
+  for result in &results {
+    table.add_row(Row::new(vec![
+      TableCell::new(&result.project_key),
+      TableCell::new(result.id.map(|v| v.to_string()).unwrap_or_default()),
+      TableCell::new(result.indexer_image_version.clone().unwrap_or_default()),
+      TableCell::new(result.query_image_version.clone().unwrap_or_default()),
+      TableCell::new(match result.status {
+        UpgradeStatus::Upgraded => "Upgraded".bold().green(),
+        UpgradeStatus::Planned => "Planned".bold().cyan(),
+        UpgradeStatus::Unchanged => "Unchanged".normal(),
+        UpgradeStatus::Skipped => "Skipped".bold().yellow(),
+        UpgradeStatus::Failed => "Failed".bold().red(),
+      }),
+      TableCell::new(result.message.clone().unwrap_or_default()),
+    ]));
+  }
+  println!("{}", table.render());
+  let count = |status: UpgradeStatus| results.iter().filter(|v| v.status == status).count();
+  println!(
+    "upgraded: {} planned: {} unchanged: {} skipped: {} failed: {}",
+    count(UpgradeStatus::Upgraded),
+    count(UpgradeStatus::Planned),
+    count(UpgradeStatus::Unchanged),
+    count(UpgradeStatus::Skipped),
+    count(UpgradeStatus::Failed),
+  );
+  Ok(())
+}
@@ -0,0 +1,245 @@
+use jaq_core::load::{self, Arena, File, Loader};
+use jaq_core::{compile, Compiler, Ctx, Filter, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+
+use crate::error::SubqueryError;
+
+/// A jq expression run by jaq, e.g. `.[] | select(.type == "stage") | .id`. unlike jq, indexing
+/// null is an error, use `.foo?` or `.foo // {}` for nullable fields.
+pub struct Query {
+  text: String,
+  filter: Filter<Native<Val>>,
+}
+
+impl std::fmt::Debug for Query {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("Query").field(&self.text).finish()
+  }
+}
+
+impl Query {
+  pub fn parse(text: impl AsRef<str>) -> color_eyre::Result<Self> {
+    let text = text.as_ref();
+    let program = File {
+      code: text,
+      path: (),
+    };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader
+      .load(&arena, program)
+      .map_err(|errors| query_error(load_errors(errors)))?;
+    let filter = Compiler::default()
+      .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+      .compile(modules)
+      .map_err(|errors| query_error(compile_errors(errors)))?;
+    Ok(Self {
+      text: text.to_string(),
+      filter,
+    })
+  }
+
+  /// Apply the query to the value, a query may produce any number of values
+  pub fn apply(&self, value: &Value) -> color_eyre::Result<Vec<Value>> {
+    let inputs = RcIter::new(core::iter::empty());
+    self
+      .filter
+      .run((Ctx::new([], &inputs), Val::from(value.clone())))
+      .map(|result| {
+        result
+          .map(Value::from)
+          .map_err(|e| query_error(e.to_string()))
+      })
+      .collect()
+  }
+}
+
+fn query_error(message: impl AsRef<str>) -> color_eyre::Report {
+  SubqueryError::Custom(format!("Wrong query: {}", message.as_ref())).into()
+}
+
+fn load_errors(errors: load::Errors<&str, ()>) -> String {
+  errors
+    .into_iter()
+    .flat_map(|(_, error)| match error {
+      load::Error::Io(v) => v
+        .into_iter()
+        .map(|(path, e)| format!("failed to load {}: {}", path, e))
+        .collect::<Vec<String>>(),
+      load::Error::Lex(v) => v
+        .into_iter()
+        .map(|(expect, found)| unexpected(expect.as_str(), found))
+        .collect(),
+      load::Error::Parse(v) => v
+        .into_iter()
+        .map(|(expect, found)| unexpected(expect.as_str(), found))
+        .collect(),
+    })
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+fn unexpected(expect: &str, found: &str) -> String {
+  if found.is_empty() {
+    format!("expected {} but the query ends", expect)
+  } else {
+    let found = found.chars().take(10).collect::<String>();
+    format!("expected {} at {}", expect, found)
+  }
+}
+
+fn compile_errors(errors: compile::Errors<&str, ()>) -> String {
+  errors
+    .into_iter()
+    .flat_map(|(_, v)| v)
+    .map(|(name, undefined)| match undefined {
+      compile::Undefined::Filter(arity) => format!("unknown function {}/{}", name, arity),
+      _ => format!("undefined {}", name),
+    })
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn deployments() -> Value {
+    json!([
+      { "id": 1, "type": "primary", "status": "running", "version": "abc", "meta": { "batch": 30 } },
+      { "id": 2, "type": "stage", "status": "error", "version": "def", "meta": null },
+      { "id": 3, "type": "stage", "status": "running", "version": "ghi", "meta": { "batch": 10 } },
+    ])
+  }
+
+  fn run(query: &str) -> Vec<Value> {
+    Query::parse(query).unwrap().apply(&deployments()).unwrap()
+  }
+
+  fn error(query: &str) -> String {
+    match Query::parse(query) {
+      Ok(v) => v.apply(&deployments()).unwrap_err().to_string(),
+      Err(e) => e.to_string(),
+    }
+  }
+
+  #[test]
+  fn test_paths() {
+    assert_eq!(run("."), vec![deployments()]);
+    assert_eq!(run(".[0].id"), vec![json!(1)]);
+    assert_eq!(run(".[-1].id"), vec![json!(3)]);
+    assert_eq!(run(".[5]"), vec![Value::Null]);
+    assert_eq!(run(".[].id"), vec![json!(1), json!(2), json!(3)]);
+    assert_eq!(run(".[0].meta.batch"), vec![json!(30)]);
+    assert_eq!(run(".[0].missing"), vec![Value::Null]);
+    assert_eq!(run(".[1].meta // {} | .batch"), vec![Value::Null]);
+    assert_eq!(run(r#".[0]["type"]"#), vec![json!("primary")]);
+    assert_eq!(run(r#".[0]."type""#), vec![json!("primary")]);
+    assert_eq!(run(".[0].meta[]"), vec![json!(30)]);
+  }
+
+  #[test]
+  fn test_pipe_and_comma() {
+    assert_eq!(run(".[] | .id"), vec![json!(1), json!(2), json!(3)]);
+    assert_eq!(run(".[0] | .id, .type"), vec![json!(1), json!("primary")]);
+  }
+
+  #[test]
+  fn test_select() {
+    assert_eq!(
+      run(r#".[] | select(.type == "stage") | .id"#),
+      vec![json!(2), json!(3)]
+    );
+    assert_eq!(
+      run(r#".[] | select(.type == "stage" and .status == "running") | .id"#),
+      vec![json!(3)]
+    );
+    assert_eq!(
+      run(r#".[] | select(.status == "error" or .id == 1) | .id"#),
+      vec![json!(1), json!(2)]
+    );
+    assert_eq!(run(".[] | select(.meta) | .id"), vec![json!(1), json!(3)]);
+    assert_eq!(run(".[] | select(.meta | not) | .id"), vec![json!(2)]);
+  }
+
+  #[test]
+  fn test_map() {
+    assert_eq!(run("map(.id)"), vec![json!([1, 2, 3])]);
+    assert_eq!(run("map(.id > 1)"), vec![json!([false, true, true])]);
+  }
+
+  #[test]
+  fn test_comparison() {
+    assert_eq!(
+      run(".[] | select(.id >= 2) | .id"),
+      vec![json!(2), json!(3)]
+    );
+    assert_eq!(run(".[] | select(.id < 2) | .id"), vec![json!(1)]);
+    assert_eq!(
+      run(".[] | select(.id != 2) | .id"),
+      vec![json!(1), json!(3)]
+    );
+    assert_eq!(run(r#".[0].version <= "abd""#), vec![json!(true)]);
+    // numbers are less than strings as jq orders values
+    assert_eq!(run(r#".[0].id < "a""#), vec![json!(true)]);
+    assert_eq!(run(".[1].meta == null"), vec![json!(true)]);
+    assert_eq!(run(".[0].id == 1.0"), vec![json!(true)]);
+    assert_eq!(run(r#".[0].id == "1""#), vec![json!(false)]);
+  }
+
+  #[test]
+  fn test_construction() {
+    assert_eq!(run("[.[] | .id]"), vec![json!([1, 2, 3])]);
+    assert_eq!(run("[]"), vec![json!([])]);
+    assert_eq!(run("{}"), vec![json!({})]);
+    assert_eq!(
+      run(".[0] | {id, commit: .version}"),
+      vec![json!({ "id": 1, "commit": "abc" })]
+    );
+    assert_eq!(
+      run(r#".[0] | {"type", batch: .meta.batch}"#),
+      vec![json!({ "type": "primary", "batch": 30 })]
+    );
+  }
+
+  #[test]
+  fn test_functions() {
+    assert_eq!(run("length"), vec![json!(3)]);
+    assert_eq!(run(".[0].type | length"), vec![json!(7)]);
+    assert_eq!(
+      run(".[0] | keys"),
+      vec![json!(["id", "meta", "status", "type", "version"])]
+    );
+    assert_eq!(run("keys"), vec![json!([0, 1, 2])]);
+    assert_eq!(run(".[0].missing? | length"), vec![json!(0)]);
+  }
+
+  #[test]
+  fn test_try() {
+    // indexing null or a number is an error, `?` suppresses it
+    assert_eq!(run(".[1].meta.batch?"), Vec::<Value>::new());
+    assert_eq!(run(".[0].id.foo?"), Vec::<Value>::new());
+    assert_eq!(run("[.[] | .meta.batch?]"), vec![json!([30, 10])]);
+    assert_eq!(
+      run(r#"[.[] | try .meta.batch catch "none"]"#),
+      vec![json!([30, "none", 10])]
+    );
+  }
+
+  #[test]
+  fn test_errors() {
+    assert!(error("").contains("expected term but the query ends"));
+    assert!(error(".[0] |").contains("expected term but the query ends"));
+    assert!(error(".[0] )").contains("expected token at )"));
+    assert!(error("foo(.)").contains("unknown function foo/1"));
+    assert!(error(r#".foo == "abc"#).contains("expected closing quote"));
+    assert!(error("{id").contains("expected closing brace"));
+    assert!(error(".[0].id[]").contains("cannot use 1 as iterable"));
+    assert!(error(".[0].id.foo").contains("cannot use 1 as iterable"));
+    assert!(error(".[1].meta.batch").contains("cannot use null as iterable"));
+    assert!(error("select(.").contains("Wrong query: "));
+  }
+}
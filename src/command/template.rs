@@ -0,0 +1,41 @@
+use handlebars::Handlebars;
+use serde_json::Value;
+
+use crate::error::SubqueryError;
+
+const TEMPLATE_NAME: &str = "format";
+
+/// A handlebars template, e.g. `{{id}} {{status}} {{queryUrl}}`. the fields are the json names
+/// of output, a missing field is rendered as empty and nothing is html escaped.
+#[derive(Debug)]
+pub struct Template {
+  registry: Handlebars<'static>,
+}
+
+impl Template {
+  pub fn parse(text: impl AsRef<str>) -> color_eyre::Result<Self> {
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(handlebars::no_escape);
+    registry
+      .register_template_string(TEMPLATE_NAME, text.as_ref())
+      .map_err(|e| SubqueryError::Custom(format!("Wrong format template: {}", e)))?;
+    Ok(Self { registry })
+  }
+
+  /// Render the value, a list is rendered one line per item
+  pub fn render(&self, value: &Value) -> color_eyre::Result<Vec<String>> {
+    let items = match value {
+      Value::Array(v) => v.iter().collect::<Vec<&Value>>(),
+      v => vec![v],
+    };
+    items
+      .into_iter()
+      .map(|item| {
+        self
+          .registry
+          .render(TEMPLATE_NAME, item)
+          .map_err(|e| SubqueryError::Custom(format!("Failed to render format: {}", e)).into())
+      })
+      .collect()
+  }
+}
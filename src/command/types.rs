@@ -1,6 +1,10 @@
+use std::path::PathBuf;
+
 use structopt::StructOpt;
 use strum::{EnumString, EnumVariantNames};
 
+use crate::command::query::Query;
+use crate::command::template::Template;
 use crate::subquery::DeploymentType;
 
 /// Generic command
@@ -10,6 +14,20 @@ pub struct Opt {
   /// Access Token, If not set will read env `SUBQUERY_TOKEN`
   #[structopt(long)]
   pub token: Option<String>,
+  /// The reason of changes, it will be recorded to local audit log
+  #[structopt(long, global = true)]
+  pub reason: Option<String>,
+  /// Filter structured output with a jq expression, raw and table output are written as json,
+  /// e.g. '.[] | select(.type == "stage") | .id'
+  #[structopt(long, alias = "query", global = true)]
+  pub jq: Option<String>,
+  /// Format structured output with a handlebars template, one line per item, e.g.
+  /// '{{id}} {{status}} {{queryUrl}}'
+  #[structopt(long, global = true)]
+  pub format: Option<String>,
+  /// Output format of all commands [json, raw, table, yaml, csv, markdown]
+  #[structopt(short, long, global = true, default_value = "raw")]
+  pub output: OutputFormat,
   /// Subquery opts
   #[structopt(flatten)]
   pub command: SubqueryOpt,
@@ -37,6 +55,138 @@ pub enum SubqueryOpt {
     #[structopt(flatten)]
     command: LogsCommand,
   },
+  /// Images
+  Images {
+    #[structopt(flatten)]
+    command: ImagesOpt,
+  },
+  /// Git repository of project
+  Repo {
+    #[structopt(flatten)]
+    command: RepoOpt,
+  },
+  /// Query local audit log of changes made by this cli
+  History {
+    #[structopt(flatten)]
+    command: HistoryCommand,
+  },
+  /// Apply a project spec file, create or update the project and deploy what has changed
+  Apply {
+    #[structopt(flatten)]
+    command: ApplyCommand,
+  },
+  /// Compare a project spec file with the live state, exit with code 1 if drifted
+  Diff {
+    #[structopt(flatten)]
+    command: DiffCommand,
+  },
+  /// Export live project and deployments as spec file
+  Export {
+    #[structopt(flatten)]
+    command: ExportCommand,
+  },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum RepoOpt {
+  /// List branches of project git repository
+  Branches {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+  },
+  /// List commits of a branch
+  Commits {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+    /// Which branch of git repository
+    #[structopt(long)]
+    branch: String,
+  },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct HistoryCommand {
+  /// Org name
+  #[structopt(long)]
+  pub org: Option<String>,
+  /// Project key
+  #[structopt(long)]
+  pub key: Option<String>,
+  /// Action [create_project, update_project, delete_project, deploy, redeploy, delete_deploy,
+  /// rebase_deployment]
+  #[structopt(long)]
+  pub action: Option<String>,
+  /// User name
+  #[structopt(long)]
+  pub user: Option<String>,
+  /// Show the latest n records
+  #[structopt(long, default_value = "20")]
+  pub limit: usize,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ApplyCommand {
+  /// Spec file, yaml or toml
+  #[structopt(short, long, parse(from_os_str), required_unless = "dir")]
+  pub file: Option<PathBuf>,
+  /// Directory of spec files, all yaml and toml files in it are applied
+  #[structopt(short, long, parse(from_os_str), conflicts_with = "file")]
+  pub dir: Option<PathBuf>,
+  /// How many projects are applied at the same time
+  #[structopt(long, default_value = "4")]
+  pub concurrency: usize,
+  /// Print what would be changed without changing anything
+  #[structopt(long)]
+  pub dry_run: bool,
+  /// Deploy even if the deploy policy in ~/.subquery/config.toml is violated
+  #[structopt(long)]
+  pub override_policy: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DiffCommand {
+  /// Spec file, yaml or toml
+  #[structopt(short, long, parse(from_os_str))]
+  pub file: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportCommand {
+  /// Org name
+  #[structopt(long)]
+  pub org: String,
+  /// Project key
+  #[structopt(long, required_unless = "all")]
+  pub key: Option<String>,
+  /// Export all projects of org
+  #[structopt(long, conflicts_with = "key")]
+  pub all: bool,
+  /// Write spec files to the directory, named <key>.yaml or <key>.toml. default print to stdout
+  #[structopt(long, parse(from_os_str))]
+  pub dir: Option<PathBuf>,
+  /// Use toml format instead of yaml
+  #[structopt(long)]
+  pub toml: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum ImagesOpt {
+  /// List image tags of a package
+  List {
+    /// Package name [@subql/node, @subql/query]
+    package: String,
+    /// Only show stable tags, pre-release tags are excluded
+    #[structopt(long)]
+    stable: bool,
+  },
 }
 
 #[derive(Debug, StructOpt)]
@@ -74,9 +224,8 @@ pub enum DeploymentOpt {
     /// Project key
     #[structopt(long)]
     key: String,
-    /// Output format
-    #[structopt(short, long, default_value = "raw")]
-    output: OutputFormat,
+    #[structopt(flatten)]
+    table: TableOpt,
   },
   /// Deploy
   Deploy {
@@ -86,15 +235,16 @@ pub enum DeploymentOpt {
     /// Project key
     #[structopt(long)]
     key: String,
-    /// Output format
-    #[structopt(short, long, default_value = "raw")]
-    output: OutputFormat,
     /// Command
     #[structopt(flatten)]
     command: DeployCommand,
-    /// If the deployment is exists will be replace to new deployment
+    /// If the deployment is exists will be replace to new deployment, the replaced deployment
+    /// will be saved to a snapshot in ~/.subquery/snapshots
     #[structopt(long)]
     force: bool,
+    /// Only print what would be deployed, do not send anything
+    #[structopt(long)]
+    dry_run: bool,
   },
   /// Delete deployment
   Delete {
@@ -122,6 +272,9 @@ pub enum DeploymentOpt {
     /// Command
     #[structopt(flatten)]
     command: DeployCommand,
+    /// Only print what would be redeployed, do not send anything
+    #[structopt(long)]
+    dry_run: bool,
   },
   /// Promote stage to product
   Promote {
@@ -134,6 +287,86 @@ pub enum DeploymentOpt {
     /// Stage deployment id. if not set it will auto detect.
     #[structopt(long)]
     id: Option<u64>,
+    /// Deploy even if the deploy policy in ~/.subquery/config.toml is violated
+    #[structopt(long)]
+    override_policy: bool,
+  },
+  /// Rollback to the previous commit and images deployed by this cli
+  Rollback {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+    /// Deployment type [stage, primary]
+    #[structopt(long = "type", default_value = "primary")]
+    type_: DeploymentType,
+    /// Only print what would be redeployed, do not send anything
+    #[structopt(long)]
+    dry_run: bool,
+    /// Deploy even if the deploy policy in ~/.subquery/config.toml is violated
+    #[structopt(long)]
+    override_policy: bool,
+  },
+  /// Restore a deployment from the snapshot saved before it was deleted
+  Restore {
+    /// Snapshot file path, or file name in ~/.subquery/snapshots
+    snapshot: String,
+    /// Only print what would be deployed, do not send anything
+    #[structopt(long)]
+    dry_run: bool,
+    /// Deploy even if the deploy policy in ~/.subquery/config.toml is violated
+    #[structopt(long)]
+    override_policy: bool,
+  },
+  /// List the commits which a new deploy would ship, from the deployed commit to branch head
+  Changelog {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+    /// Deployment type [stage, primary]
+    #[structopt(long = "type", default_value = "primary")]
+    type_: DeploymentType,
+    /// Which branch of git repository
+    #[structopt(long)]
+    branch: String,
+    /// Output markdown, for release notes
+    #[structopt(long)]
+    markdown: bool,
+  },
+  /// Compare stage and primary deployment
+  Diff {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+  },
+  /// Report deployments of all projects in an org which images are behind the newest
+  Outdated {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Also show the deployments which are up to date
+    #[structopt(long)]
+    all: bool,
+    /// Max number of projects to query at the same time
+    #[structopt(long, default_value = "8")]
+    concurrency: usize,
+  },
+  /// Upgrade images of deployments for many projects, the commit and settings are kept
+  Upgrade {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Command
+    #[structopt(flatten)]
+    command: UpgradeCommand,
   },
   /// Query sync status
   SyncStatus {
@@ -157,10 +390,17 @@ pub enum DeploymentOpt {
 
 #[derive(Debug, StructOpt)]
 pub struct DeployCommand {
-  /// Which branch of git repository
+  /// Which branch of git repository, required by deploy. redeploy only needs it to resolve
+  /// --commit from branch history
   #[structopt(long)]
-  pub branch: String,
-  /// The commit of branch, default is latest commit id
+  pub branch: Option<String>,
+  /// Deploy the current branch and HEAD commit of the git checkout in current directory, the
+  /// remote must be the project repository and HEAD must be pushed
+  #[structopt(long, conflicts_with_all = &["branch", "commit"])]
+  pub from_local: bool,
+  /// The commit of branch, a commit sha (at least 4 characters), HEAD~n, or @stage/@primary for
+  /// the commit currently deployed, tags are not supported. default is latest commit id, redeploy
+  /// keeps the current commit if not set
   #[structopt(long)]
   pub commit: Option<String>,
   /// Override Network endpoint
@@ -169,10 +409,12 @@ pub struct DeployCommand {
   /// Override Dictionary endpoint
   #[structopt(long)]
   pub dict_endpoint: Option<String>,
-  /// Indexer Version (@subql/node)
+  /// Indexer Version (@subql/node), a tag, semver range (^0.25, ~1.2) or latest-stable.
+  /// default is latest-stable
   #[structopt(long)]
   pub indexer_image_version: Option<String>,
-  /// Query Version (@subql/query)
+  /// Query Version (@subql/query), a tag, semver range (^0.8, ~0.8.0) or latest-stable.
+  /// default is latest-stable
   #[structopt(long)]
   pub query_image_version: Option<String>,
   /// Deployment type [stage, primary]
@@ -181,12 +423,63 @@ pub struct DeployCommand {
   /// Sub folder
   #[structopt(long)]
   pub sub_folder: Option<String>,
-  /// Batch size for indexer
-  #[structopt(long, default_value = "30")]
-  pub batch_size: u32,
-  /// Subscription activation
+  /// Batch size for indexer, default is 30. redeploy will keep the current value if not set
+  #[structopt(long)]
+  pub batch_size: Option<u32>,
+  /// Subscription activation, default is false. redeploy will keep the current value if neither
+  /// --subscription nor --no-subscription is set
   #[structopt(long)]
   pub subscription: bool,
+  /// Subscription deactivation
+  #[structopt(long, conflicts_with = "subscription")]
+  pub no_subscription: bool,
+  /// Deploy even if the deploy policy in ~/.subquery/config.toml is violated
+  #[structopt(long)]
+  pub override_policy: bool,
+  /// Validate project.yaml, schema.graphql, mapping files and endpoints of the local checkout
+  /// before deploy
+  #[structopt(long)]
+  pub validate: bool,
+  /// The local checkout to validate, the sub folder is joined. default is the root of git
+  /// checkout in current directory
+  #[structopt(long, parse(from_os_str), requires = "validate")]
+  pub local_path: Option<PathBuf>,
+}
+
+impl DeployCommand {
+  /// The subscription activation passed by --subscription or --no-subscription
+  pub fn subscription(&self) -> Option<bool> {
+    match (self.subscription, self.no_subscription) {
+      (true, _) => Some(true),
+      (_, true) => Some(false),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UpgradeCommand {
+  /// Only upgrade the projects which key matched this glob pattern, e.g. pangolin-*
+  #[structopt(long)]
+  pub projects: Option<String>,
+  /// Indexer Version (@subql/node), a tag, semver range (^0.25, ~1.2) or latest-stable
+  #[structopt(long)]
+  pub indexer_image_version: Option<String>,
+  /// Query Version (@subql/query), a tag, semver range (^0.8, ~0.8.0) or latest-stable
+  #[structopt(long)]
+  pub query_image_version: Option<String>,
+  /// Deployment type [stage, primary]
+  #[structopt(long = "type", default_value = "stage")]
+  pub type_: DeploymentType,
+  /// Max number of projects to upgrade at the same time
+  #[structopt(long, default_value = "4")]
+  pub concurrency: usize,
+  /// Only print what would be upgraded, do not send anything
+  #[structopt(long)]
+  pub dry_run: bool,
+  /// Upgrade even if the deploy policy in ~/.subquery/config.toml is violated
+  #[structopt(long)]
+  pub override_policy: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -261,16 +554,80 @@ pub enum ProjectOpt {
     /// Org name
     #[structopt(long)]
     org: String,
-    /// Output format
-    #[structopt(short, long, default_value = "raw")]
-    output: OutputFormat,
+    #[structopt(flatten)]
+    table: TableOpt,
+  },
+  /// Show a project and its deployment
+  Show {
+    /// Org name
+    #[structopt(long)]
+    org: String,
+    /// Project key
+    #[structopt(long)]
+    key: String,
+    /// Show all deployments of project, not only the deployment of project
+    #[structopt(long)]
+    with_deployments: bool,
   },
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+/// Columns and sorting of table output
+#[derive(Debug, Default, StructOpt)]
+pub struct TableOpt {
+  /// Columns of table output, comma separated, e.g. id,type,status
+  #[structopt(long, use_delimiter = true)]
+  pub columns: Option<Vec<String>>,
+  /// Sort rows of table output by the column, prefix `-` for descending, e.g. -created-at
+  #[structopt(long, allow_hyphen_values = true)]
+  pub sort_by: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumString, EnumVariantNames)]
 #[strum(serialize_all = "kebab_case")]
 pub enum OutputFormat {
   Json,
   Raw,
   Table,
+  Yaml,
+  Csv,
+  Markdown,
+}
+
+/// How to write the output of a command, the output format with the `--jq` query and `--format`
+/// template applied to structured output
+#[derive(Debug)]
+pub struct Output {
+  pub format: OutputFormat,
+  pub query: Option<Query>,
+  pub template: Option<Template>,
+}
+
+impl Output {
+  /// Parse the query and template of options
+  pub fn new(
+    format: OutputFormat,
+    query: Option<&str>,
+    template: Option<&str>,
+  ) -> color_eyre::Result<Self> {
+    Ok(Self {
+      format,
+      query: query.map(Query::parse).transpose()?,
+      template: template.map(Template::parse).transpose()?,
+    })
+  }
+
+  /// Whether the output is filtered by a query or formatted by a template
+  pub fn is_filtered(&self) -> bool {
+    self.query.is_some() || self.template.is_some()
+  }
+}
+
+impl Default for Output {
+  fn default() -> Self {
+    Self {
+      format: OutputFormat::Raw,
+      query: None,
+      template: None,
+    }
+  }
 }
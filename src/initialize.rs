@@ -13,8 +13,9 @@ fn init_log() -> color_eyre::Result<()> {
 
   let subscriber = tracing_subscriber::FmtSubscriber::builder()
     // all spans/events with a level higher than TRACE (e.g, debug, info, warn, etc.)
-    // will be written to stdout.
+    // will be written to stderr, stdout is kept for the output of commands.
     .with_max_level(Level::TRACE)
+    .with_writer(std::io::stderr)
     .with_env_filter("trace,hyper=error")
     // builds the subscriber.
     .finish();
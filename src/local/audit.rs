@@ -0,0 +1,68 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Local audit log, every change sent by this cli will be recorded.
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+  path: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditRecord {
+  pub time: DateTime<Utc>,
+  /// The user name of access token
+  pub user: String,
+  /// Api action, e.g. deploy, redeploy, delete_project
+  pub action: String,
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  /// The request payload
+  pub payload: serde_json::Value,
+  pub success: bool,
+  /// Error message if failed
+  pub error: Option<String>,
+  pub reason: Option<String>,
+}
+
+impl AuditLog {
+  pub fn new() -> color_eyre::Result<Self> {
+    let path = super::home()?.join("history").join("audit.jsonl");
+    Ok(Self { path })
+  }
+}
+
+impl AuditLog {
+  /// Append a record to audit log
+  pub fn append(&self, record: &AuditRecord) -> color_eyre::Result<()> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&self.path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+  }
+
+  /// All records, the oldest is first
+  pub fn records(&self) -> color_eyre::Result<Vec<AuditRecord>> {
+    if !self.path.exists() {
+      return Ok(vec![]);
+    }
+    let file = std::fs::File::open(&self.path)?;
+    let mut records = vec![];
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+  }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::local::DeployPolicy;
+
+/// Local config file, `~/.subquery/config.toml`
+///
+/// ```toml
+/// # default policy for all projects
+/// [policy]
+/// require_stage_before_primary = true
+///
+/// [projects."fewensa/project-key".policy]
+/// protected_branch_for_primary = true
+/// require_newer_commit = true
+/// deny_images = ["*-rc*"]
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LocalConfig {
+  /// Default policy for all projects
+  pub policy: Option<DeployPolicy>,
+  /// Project config, the key is project key, e.g. fewensa/project-key
+  #[serde(default)]
+  pub projects: HashMap<String, ProjectConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+  pub policy: Option<DeployPolicy>,
+}
+
+impl LocalConfig {
+  /// Load config file, return default config if the file not exists
+  pub fn load() -> color_eyre::Result<Self> {
+    let path = super::home()?.join("config.toml");
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+  }
+}
+
+impl LocalConfig {
+  /// The deploy policy of project, the project policy overrides the default policy
+  pub fn policy(&self, project_key: impl AsRef<str>) -> DeployPolicy {
+    let default = self.policy.clone().unwrap_or_default();
+    match self
+      .projects
+      .get(project_key.as_ref())
+      .and_then(|v| v.policy.clone())
+    {
+      Some(policy) => policy.merge(default),
+      None => default,
+    }
+  }
+}
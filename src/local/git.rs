@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::SubqueryError;
+
+/// The git checkout of current directory
+#[derive(Clone, Debug)]
+pub struct LocalRepository {
+  /// Remote url of current branch, e.g. https://github.com/fewensa/subql.git
+  pub remote_url: String,
+  /// Current branch
+  pub branch: String,
+  /// HEAD commit sha
+  pub head: String,
+  /// Whether the working tree has uncommitted changes
+  pub dirty: bool,
+}
+
+impl LocalRepository {
+  /// Read the git checkout of current directory
+  pub fn current() -> color_eyre::Result<Self> {
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+    if branch == "HEAD" {
+      return Err(
+        SubqueryError::Custom("The local checkout is in detached HEAD state".to_string()).into(),
+      );
+    }
+    let head = git(&["rev-parse", "HEAD"])?;
+    let remote = git(&["config", &format!("branch.{}.remote", branch)])
+      .unwrap_or_else(|_| "origin".to_string());
+    let remote_url = git(&["remote", "get-url", &remote])?;
+    let dirty = !git(&["status", "--porcelain"])?.is_empty();
+    Ok(Self {
+      remote_url,
+      branch,
+      head,
+      dirty,
+    })
+  }
+}
+
+/// The root of git checkout in current directory
+pub fn git_root() -> color_eyre::Result<PathBuf> {
+  Ok(PathBuf::from(git(&["rev-parse", "--show-toplevel"])?))
+}
+
+/// Repository name of git url, support https and ssh url
+///
+/// - https://github.com/fewensa/subql.git -> fewensa/subql
+/// - git@github.com:fewensa/subql.git -> fewensa/subql
+pub fn repository_name(url: impl AsRef<str>) -> Option<String> {
+  let url = url.as_ref().trim();
+  let url = url.split('?').next().unwrap_or(url);
+  let url = url.trim_end_matches('/');
+  let url = url.strip_suffix(".git").unwrap_or(url);
+  let parts = url.split(['/', ':']).collect::<Vec<&str>>();
+  if parts.len() < 2 {
+    return None;
+  }
+  let org = parts[parts.len() - 2];
+  let repo = parts[parts.len() - 1];
+  if org.is_empty() || repo.is_empty() {
+    return None;
+  }
+  Some(format!("{}/{}", org, repo).to_lowercase())
+}
+
+fn git(args: &[&str]) -> color_eyre::Result<String> {
+  let output = Command::new("git").args(args).output()?;
+  if !output.status.success() {
+    return Err(
+      SubqueryError::Custom(format!(
+        "Failed to run git {}: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+      ))
+      .into(),
+    );
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_https_url() {
+    let name = Some("fewensa/subql".to_string());
+    assert_eq!(
+      repository_name("https://github.com/fewensa/subql.git"),
+      name
+    );
+    assert_eq!(repository_name("https://github.com/fewensa/subql"), name);
+    assert_eq!(repository_name("https://github.com/fewensa/subql/"), name);
+    assert_eq!(
+      repository_name("https://github.com/Fewensa/SubQL?tab=readme"),
+      name
+    );
+  }
+
+  #[test]
+  fn test_ssh_url() {
+    let name = Some("fewensa/subql".to_string());
+    assert_eq!(repository_name("git@github.com:fewensa/subql.git"), name);
+    assert_eq!(
+      repository_name("ssh://git@github.com/fewensa/subql.git"),
+      name
+    );
+    assert_eq!(
+      repository_name("https://github.com/fewensa/subql.git"),
+      repository_name("git@github.com:fewensa/subql.git")
+    );
+  }
+
+  #[test]
+  fn test_wrong_url() {
+    assert_eq!(repository_name(""), None);
+    assert_eq!(repository_name("subql"), None);
+    assert_eq!(repository_name("https://github.com/"), None);
+  }
+}
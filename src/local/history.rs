@@ -0,0 +1,236 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum::AsRefStr;
+
+use crate::subquery::{CreateDeployRequest, DeploymentType};
+
+/// Local deployment history, every deployment made by this cli will be recorded.
+#[derive(Clone, Debug)]
+pub struct DeploymentHistory {
+  path: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeploymentRecord {
+  pub time: DateTime<Utc>,
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  /// Deployment id, the promoted stage deployment id for promote
+  pub id: Option<u64>,
+  pub action: DeploymentAction,
+  /// The request which is sent, commit and image versions are resolved
+  pub deployment: CreateDeployRequest,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum DeploymentAction {
+  #[serde(rename = "deploy")]
+  Deploy,
+  #[serde(rename = "redeploy")]
+  Redeploy,
+  #[serde(rename = "promote")]
+  Promote,
+  #[serde(rename = "upgrade")]
+  Upgrade,
+  #[serde(rename = "rollback")]
+  Rollback,
+  #[serde(rename = "restore")]
+  Restore,
+}
+
+impl DeploymentRecord {
+  pub fn new(
+    project_key: impl AsRef<str>,
+    id: Option<u64>,
+    action: DeploymentAction,
+    deployment: CreateDeployRequest,
+  ) -> Self {
+    Self {
+      time: Utc::now(),
+      project_key: project_key.as_ref().to_string(),
+      id,
+      action,
+      deployment,
+    }
+  }
+
+  /// Whether deploy the same commit and images
+  pub fn same_version(
+    &self,
+    commit: &str,
+    indexer_image_version: Option<&str>,
+    query_image_version: Option<&str>,
+  ) -> bool {
+    self.deployment.commit.as_deref() == Some(commit)
+      && self.deployment.indexer_image_version.as_deref() == indexer_image_version
+      && self.deployment.query_image_version.as_deref() == query_image_version
+  }
+
+  /// Whether the other record deploys the same commit and images
+  pub fn same_deployment(&self, other: &DeploymentRecord) -> bool {
+    other.deployment.commit.as_deref().is_some_and(|commit| {
+      self.same_version(
+        commit,
+        other.deployment.indexer_image_version.as_deref(),
+        other.deployment.query_image_version.as_deref(),
+      )
+    })
+  }
+}
+
+/// The record to rollback to, the newest one not deployed currently. a rollback record moves back
+/// to the version it deployed and drops the versions after it, e.g. deploy A, B, C and rollback
+/// to B, the next rollback is A not C.
+pub fn rollback_target(
+  records: &[DeploymentRecord],
+  is_current: impl Fn(&DeploymentRecord) -> bool,
+) -> Option<&DeploymentRecord> {
+  let mut stack: Vec<&DeploymentRecord> = vec![];
+  for record in records {
+    if record.action == DeploymentAction::Rollback {
+      while let Some(top) = stack.last() {
+        if top.same_deployment(record) {
+          break;
+        }
+        stack.pop();
+      }
+      if !stack.is_empty() {
+        continue;
+      }
+    }
+    stack.push(record);
+  }
+  stack.into_iter().rev().find(|record| !is_current(record))
+}
+
+impl DeploymentHistory {
+  pub fn new() -> color_eyre::Result<Self> {
+    let path = super::home()?.join("history").join("deployments.jsonl");
+    Ok(Self { path })
+  }
+}
+
+impl DeploymentHistory {
+  /// Append a record to history
+  pub fn append(&self, record: &DeploymentRecord) -> color_eyre::Result<()> {
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&self.path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+  }
+
+  /// All records of this project and deployment type, the oldest is first
+  pub fn records(
+    &self,
+    project_key: impl AsRef<str>,
+    type_: &DeploymentType,
+  ) -> color_eyre::Result<Vec<DeploymentRecord>> {
+    if !self.path.exists() {
+      return Ok(vec![]);
+    }
+    let file = std::fs::File::open(&self.path)?;
+    let mut records = vec![];
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      let record: DeploymentRecord = serde_json::from_str(&line)?;
+      if record.project_key == project_key.as_ref() && &record.deployment.type_ == type_ {
+        records.push(record);
+      }
+    }
+    Ok(records)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::subquery::fixtures;
+
+  fn record(action: DeploymentAction, commit: &str) -> DeploymentRecord {
+    let deployment = fixtures::deploy_request(DeploymentType::Primary, commit, "v0.25.3");
+    DeploymentRecord::new("fewensa/project", Some(1), action, deployment)
+  }
+
+  fn target<'a>(records: &'a [DeploymentRecord], current: &str) -> Option<&'a str> {
+    rollback_target(records, |record| {
+      record.same_version(current, Some("v0.25.3"), Some("v0.8.0"))
+    })
+    .and_then(|record| record.deployment.commit.as_deref())
+  }
+
+  #[test]
+  fn test_rollback_to_previous() {
+    let records = vec![
+      record(DeploymentAction::Deploy, "a"),
+      record(DeploymentAction::Redeploy, "b"),
+      record(DeploymentAction::Redeploy, "c"),
+    ];
+    assert_eq!(target(&records, "c"), Some("b"));
+  }
+
+  #[test]
+  fn test_rollback_twice_skips_rolled_back() {
+    let mut records = vec![
+      record(DeploymentAction::Deploy, "a"),
+      record(DeploymentAction::Redeploy, "b"),
+      record(DeploymentAction::Redeploy, "c"),
+      record(DeploymentAction::Rollback, "b"),
+    ];
+    assert_eq!(target(&records, "b"), Some("a"));
+    records.push(record(DeploymentAction::Rollback, "a"));
+    assert_eq!(target(&records, "a"), None);
+  }
+
+  #[test]
+  fn test_rollback_after_new_deploy() {
+    let records = vec![
+      record(DeploymentAction::Deploy, "a"),
+      record(DeploymentAction::Redeploy, "b"),
+      record(DeploymentAction::Redeploy, "c"),
+      record(DeploymentAction::Rollback, "b"),
+      record(DeploymentAction::Redeploy, "d"),
+    ];
+    assert_eq!(target(&records, "d"), Some("b"));
+  }
+
+  #[test]
+  fn test_rollback_skips_same_version() {
+    let records = vec![
+      record(DeploymentAction::Deploy, "a"),
+      record(DeploymentAction::Redeploy, "b"),
+      record(DeploymentAction::Upgrade, "b"),
+    ];
+    assert_eq!(target(&records, "b"), Some("a"));
+  }
+
+  #[test]
+  fn test_rollback_unknown_target() {
+    // the rollback target is not in the records, it starts a new history
+    let records = vec![
+      record(DeploymentAction::Deploy, "a"),
+      record(DeploymentAction::Rollback, "x"),
+      record(DeploymentAction::Redeploy, "b"),
+    ];
+    assert_eq!(target(&records, "b"), Some("x"));
+  }
+
+  #[test]
+  fn test_rollback_without_current() {
+    let records = vec![record(DeploymentAction::Deploy, "a")];
+    assert_eq!(target(&records, "z"), Some("a"));
+    assert_eq!(target(&[], "z"), None);
+  }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The manifest file name of project
+pub const PROJECT_MANIFEST: &str = "project.yaml";
+
+/// The project manifest `project.yaml`, only the fields needed to validate are kept
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectManifest {
+  pub spec_version: String,
+  pub schema: FileReference,
+  pub network: ManifestNetwork,
+  pub data_sources: Vec<ManifestDataSource>,
+}
+
+/// A file referenced by manifest, `./schema.graphql` or `file: ./schema.graphql`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FileReference {
+  Path(String),
+  File { file: String },
+}
+
+impl FileReference {
+  pub fn path(&self) -> &str {
+    match self {
+      FileReference::Path(v) => v,
+      FileReference::File { file } => file,
+    }
+  }
+}
+
+/// One endpoint or a list of endpoints
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestEndpoint {
+  One(String),
+  Many(Vec<String>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestNetwork {
+  pub endpoint: Option<ManifestEndpoint>,
+  pub dictionary: Option<String>,
+  pub chaintypes: Option<FileReference>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDataSource {
+  pub kind: String,
+  pub start_block: Option<u64>,
+  pub mapping: ManifestMapping,
+  /// Processor of custom data source
+  pub processor: Option<FileReference>,
+  /// Assets of custom data source
+  pub assets: Option<HashMap<String, FileReference>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestMapping {
+  /// The mapping file, not exists in spec version 0.0.1
+  pub file: Option<String>,
+  pub handlers: Vec<ManifestHandler>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestHandler {
+  pub handler: String,
+  pub kind: String,
+}
+
+/// Validate the project in local checkout before deploy, return the errors. the endpoint and
+/// dictionary endpoint overridden by deploy are checked instead of the manifest ones.
+pub fn validate_project(
+  dir: impl AsRef<Path>,
+  endpoint: Option<&str>,
+  dict_endpoint: Option<&str>,
+) -> Vec<String> {
+  let dir = dir.as_ref();
+  let path = dir.join(PROJECT_MANIFEST);
+  let content = match std::fs::read_to_string(&path) {
+    Ok(v) => v,
+    Err(e) => return vec![format!("Failed to read {}: {}", path.display(), e)],
+  };
+  let manifest: ProjectManifest = match serde_yaml::from_str(&content) {
+    Ok(v) => v,
+    Err(e) => return vec![format!("Failed to parse {}: {}", PROJECT_MANIFEST, e)],
+  };
+
+  let mut errors = vec![];
+
+  let schema = manifest.schema.path();
+  match std::fs::read_to_string(dir.join(schema)) {
+    Ok(content) => {
+      if let Err(e) = graphql_parser::parse_schema::<String>(&content) {
+        errors.push(format!("Failed to parse schema {}: {}", schema, e));
+      }
+    }
+    Err(e) => errors.push(format!("schema: failed to read {}: {}", schema, e)),
+  }
+
+  if let Some(chaintypes) = &manifest.network.chaintypes {
+    check_file(dir, "network.chaintypes", chaintypes.path(), &mut errors);
+  }
+  for (ix, data_source) in manifest.data_sources.iter().enumerate() {
+    let name = format!("dataSources[{}]", ix);
+    if let Some(file) = &data_source.mapping.file {
+      check_file(dir, format!("{}.mapping.file", name), file, &mut errors);
+    }
+    if data_source.mapping.handlers.is_empty() {
+      errors.push(format!("{}.mapping.handlers: no handler defined", name));
+    }
+    if let Some(processor) = &data_source.processor {
+      check_file(
+        dir,
+        format!("{}.processor", name),
+        processor.path(),
+        &mut errors,
+      );
+    }
+    for (asset, file) in data_source.assets.iter().flatten() {
+      check_file(
+        dir,
+        format!("{}.assets.{}", name, asset),
+        file.path(),
+        &mut errors,
+      );
+    }
+  }
+
+  let endpoints = match (endpoint, &manifest.network.endpoint) {
+    (Some(v), _) => vec![("--endpoint".to_string(), v.to_string())],
+    (None, Some(ManifestEndpoint::One(v))) => vec![("network.endpoint".to_string(), v.clone())],
+    (None, Some(ManifestEndpoint::Many(v))) => v
+      .iter()
+      .enumerate()
+      .map(|(ix, v)| (format!("network.endpoint[{}]", ix), v.clone()))
+      .collect(),
+    (None, None) => vec![],
+  };
+  if endpoints.is_empty() {
+    errors.push("network.endpoint: no endpoint defined".to_string());
+  }
+  for (name, endpoint) in endpoints {
+    check_url(name, endpoint, &["ws", "wss", "http", "https"], &mut errors);
+  }
+  match (dict_endpoint, &manifest.network.dictionary) {
+    (Some(v), _) => check_url("--dict-endpoint", v, &["http", "https"], &mut errors),
+    (None, Some(v)) => check_url("network.dictionary", v, &["http", "https"], &mut errors),
+    (None, None) => {}
+  }
+  errors
+}
+
+fn check_file(dir: &Path, name: impl AsRef<str>, file: &str, errors: &mut Vec<String>) {
+  let path: PathBuf = dir.join(file);
+  if !path.is_file() {
+    errors.push(format!("{}: file {} not found", name.as_ref(), file));
+  }
+}
+
+fn check_url(
+  name: impl AsRef<str>,
+  endpoint: impl AsRef<str>,
+  schemes: &[&str],
+  errors: &mut Vec<String>,
+) {
+  let name = name.as_ref();
+  let endpoint = endpoint.as_ref();
+  match url::Url::parse(endpoint) {
+    Ok(url) if !schemes.contains(&url.scheme()) => errors.push(format!(
+      "{}: the scheme of {} must be one of {}",
+      name,
+      endpoint,
+      schemes.join(", ")
+    )),
+    Ok(url) if url.host_str().unwrap_or_default().is_empty() => {
+      errors.push(format!("{}: missing host in {}", name, endpoint))
+    }
+    Ok(_) => {}
+    Err(e) => errors.push(format!("{}: wrong endpoint {}: {}", name, endpoint, e)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MANIFEST: &str = r#"
+specVersion: 0.2.0
+schema:
+  file: ./schema.graphql
+network:
+  endpoint: wss://polkadot.api.onfinality.io/public-ws
+  dictionary: https://api.subquery.network/sq/subquery/dictionary-polkadot
+dataSources:
+  - kind: substrate/Runtime
+    startBlock: 1
+    mapping:
+      file: ./dist/index.js
+      handlers:
+        - handler: handleBlock
+          kind: substrate/BlockHandler
+"#;
+
+  const SCHEMA: &str = "type StarterEntity @entity {\n  id: ID!\n}\n";
+
+  /// A valid project in a temp dir, the files can be removed or overwritten by tests
+  fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join(PROJECT_MANIFEST), MANIFEST).unwrap();
+    std::fs::write(dir.path().join("schema.graphql"), SCHEMA).unwrap();
+    std::fs::create_dir(dir.path().join("dist")).unwrap();
+    std::fs::write(dir.path().join("dist").join("index.js"), "").unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_valid_project() {
+    let dir = project();
+    assert!(validate_project(dir.path(), None, None).is_empty());
+  }
+
+  #[test]
+  fn test_missing_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let errors = validate_project(dir.path(), None, None);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("Failed to read"), "{:?}", errors);
+  }
+
+  #[test]
+  fn test_missing_schema() {
+    let dir = project();
+    std::fs::remove_file(dir.path().join("schema.graphql")).unwrap();
+    let errors = validate_project(dir.path(), None, None);
+    assert_eq!(errors.len(), 1);
+    assert!(
+      errors[0].starts_with("schema: failed to read ./schema.graphql"),
+      "{:?}",
+      errors
+    );
+  }
+
+  #[test]
+  fn test_invalid_schema() {
+    let dir = project();
+    std::fs::write(dir.path().join("schema.graphql"), "type StarterEntity {").unwrap();
+    let errors = validate_project(dir.path(), None, None);
+    assert_eq!(errors.len(), 1);
+    assert!(
+      errors[0].starts_with("Failed to parse schema ./schema.graphql"),
+      "{:?}",
+      errors
+    );
+  }
+
+  #[test]
+  fn test_missing_mapping_file() {
+    let dir = project();
+    std::fs::remove_file(dir.path().join("dist").join("index.js")).unwrap();
+    let errors = validate_project(dir.path(), None, None);
+    assert_eq!(
+      errors,
+      vec!["dataSources[0].mapping.file: file ./dist/index.js not found".to_string()]
+    );
+  }
+
+  #[test]
+  fn test_bad_endpoint_scheme() {
+    let dir = project();
+    let errors = validate_project(
+      dir.path(),
+      Some("ftp://polkadot.api.onfinality.io"),
+      Some("wss://api.subquery.network/sq/subquery/dictionary-polkadot"),
+    );
+    assert_eq!(
+      errors,
+      vec![
+        "--endpoint: the scheme of ftp://polkadot.api.onfinality.io must be one of ws, wss, http, https"
+          .to_string(),
+        "--dict-endpoint: the scheme of wss://api.subquery.network/sq/subquery/dictionary-polkadot must be one of http, https"
+          .to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_wrong_endpoint() {
+    let dir = project();
+    let errors = validate_project(dir.path(), Some("not an url"), None);
+    assert_eq!(errors.len(), 1);
+    assert!(
+      errors[0].starts_with("--endpoint: wrong endpoint not an url"),
+      "{:?}",
+      errors
+    );
+  }
+}
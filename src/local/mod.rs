@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use crate::error::SubqueryError;
+
+pub use self::audit::*;
+pub use self::config::*;
+pub use self::git::*;
+pub use self::history::*;
+pub use self::manifest::*;
+pub use self::policy::*;
+pub use self::snapshot::*;
+pub use self::spec::*;
+
+mod audit;
+mod config;
+mod git;
+mod history;
+mod manifest;
+mod policy;
+mod snapshot;
+mod spec;
+
+/// The directory of local state, default is `~/.subquery`, can be changed by env `SUBQUERY_HOME`
+pub fn home() -> color_eyre::Result<PathBuf> {
+  if let Ok(v) = std::env::var("SUBQUERY_HOME") {
+    return Ok(PathBuf::from(v));
+  }
+  let home = dirs::home_dir()
+    .ok_or_else(|| SubqueryError::Custom("Can not find home directory".to_string()))?;
+  Ok(home.join(".subquery"))
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+
+use crate::subquery::{Branch, Commit, CreateDeployRequest, Deployment, DeploymentType};
+
+/// Deploy policy, the deploy which violates the policy will be blocked
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DeployPolicy {
+  /// Primary deployment only deploy from protected branches
+  pub protected_branch_for_primary: Option<bool>,
+  /// A stage deployment must exist before deploy primary
+  pub require_stage_before_primary: Option<bool>,
+  /// The commit must not be older than the current deployed commit
+  pub require_newer_commit: Option<bool>,
+  /// Denied image tags, glob pattern supported, e.g. `*-rc*`
+  pub deny_images: Option<Vec<String>>,
+}
+
+/// The state used to check deploy policy
+pub struct PolicyContext<'a> {
+  pub deployment: &'a CreateDeployRequest,
+  /// The branch of deploy or the branch which contains the commit, none if not known, then the
+  /// protected branch rule is not checked
+  pub branch: Option<&'a Branch>,
+  /// The latest commits of branch, the newest is first. the newer commit rule is not checked for
+  /// commits not in it
+  pub commits: &'a [Commit],
+  /// All deployments of project
+  pub deployments: &'a [Deployment],
+}
+
+impl DeployPolicy {
+  /// Fill the rules not set by other policy
+  pub fn merge(self, other: DeployPolicy) -> Self {
+    Self {
+      protected_branch_for_primary: self
+        .protected_branch_for_primary
+        .or(other.protected_branch_for_primary),
+      require_stage_before_primary: self
+        .require_stage_before_primary
+        .or(other.require_stage_before_primary),
+      require_newer_commit: self.require_newer_commit.or(other.require_newer_commit),
+      deny_images: self.deny_images.or(other.deny_images),
+    }
+  }
+
+  /// Check the deploy, return the violations
+  pub fn check(&self, context: &PolicyContext) -> Vec<String> {
+    let mut violations = vec![];
+    let deployment = context.deployment;
+    let primary = deployment.type_ == DeploymentType::Primary;
+
+    if primary && self.protected_branch_for_primary.unwrap_or(false) {
+      if let Some(branch) = context.branch.filter(|item| !item.protected) {
+        violations.push(format!(
+          "Primary deployment only deploy from protected branches, the branch {} is not protected",
+          branch.name
+        ));
+      }
+    }
+
+    if primary && self.require_stage_before_primary.unwrap_or(false) {
+      let stage = context
+        .deployments
+        .iter()
+        .any(|item| item.type_ == DeploymentType::Stage);
+      if !stage {
+        violations.push("A stage deployment must exist before deploy primary".to_string());
+      }
+    }
+
+    if self.require_newer_commit.unwrap_or(false) {
+      let current = context
+        .deployments
+        .iter()
+        .find(|item| item.type_ == deployment.type_);
+      // the same commit is not older, e.g. upgrade images only
+      let current = current.filter(|item| Some(&item.version) != deployment.commit.as_ref());
+      if let (Some(current), Some(commit)) = (current, &deployment.commit) {
+        let position = |sha: &str| context.commits.iter().position(|item| item.sha == sha);
+        match (position(&current.version), position(commit)) {
+          // the newest is first, larger position is older
+          (Some(current_position), Some(position)) if position > current_position => violations
+            .push(format!(
+              "The commit {} is older than the current deployed commit {}",
+              commit, current.version
+            )),
+          // the current deployed commit is older than the branch history, or the commit is not
+          // in it which can not be checked
+          _ => {}
+        }
+      }
+    }
+
+    if let Some(deny_images) = &self.deny_images {
+      let images = [
+        ("@subql/node", &deployment.indexer_image_version),
+        ("@subql/query", &deployment.query_image_version),
+      ];
+      for (name, version) in images {
+        let version = match version {
+          Some(v) => v,
+          None => continue,
+        };
+        let denied = deny_images
+          .iter()
+          .any(|pattern| match glob::Pattern::new(pattern) {
+            Ok(pattern) => pattern.matches(version),
+            Err(_) => pattern == version,
+          });
+        if denied {
+          violations.push(format!("The image {}:{} is denied", name, version));
+        }
+      }
+    }
+
+    violations
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::subquery::fixtures::{self, branch, deploy_request as request, deployment};
+
+  fn commits() -> Vec<Commit> {
+    fixtures::commits(&["c", "b", "a"])
+  }
+
+  fn check(
+    policy: &DeployPolicy,
+    deployment: &CreateDeployRequest,
+    branch: Option<&Branch>,
+    deployments: &[Deployment],
+  ) -> Vec<String> {
+    let commits = commits();
+    policy.check(&PolicyContext {
+      deployment,
+      branch,
+      commits: &commits,
+      deployments,
+    })
+  }
+
+  #[test]
+  fn test_empty_policy() {
+    let deployment = request(DeploymentType::Primary, "a", "v1.0.0-rc1");
+    assert!(check(&DeployPolicy::default(), &deployment, None, &[]).is_empty());
+  }
+
+  #[test]
+  fn test_protected_branch_for_primary() {
+    let policy = DeployPolicy {
+      protected_branch_for_primary: Some(true),
+      ..Default::default()
+    };
+    let primary = request(DeploymentType::Primary, "c", "v0.25.3");
+    let main = branch("main", true, "c");
+    let feature = branch("feature", false, "c");
+    assert!(check(&policy, &primary, Some(&main), &[]).is_empty());
+    assert_eq!(check(&policy, &primary, Some(&feature), &[]).len(), 1);
+    // the branch is not known
+    assert!(check(&policy, &primary, None, &[]).is_empty());
+    // stage is not limited
+    let stage = request(DeploymentType::Stage, "c", "v0.25.3");
+    assert!(check(&policy, &stage, Some(&feature), &[]).is_empty());
+  }
+
+  #[test]
+  fn test_require_stage_before_primary() {
+    let policy = DeployPolicy {
+      require_stage_before_primary: Some(true),
+      ..Default::default()
+    };
+    let primary = request(DeploymentType::Primary, "c", "v0.25.3");
+    assert_eq!(check(&policy, &primary, None, &[]).len(), 1);
+    assert_eq!(
+      check(&policy, &primary, None, &[deployment("primary", "b")]).len(),
+      1
+    );
+    assert!(check(&policy, &primary, None, &[deployment("stage", "c")]).is_empty());
+    let stage = request(DeploymentType::Stage, "c", "v0.25.3");
+    assert!(check(&policy, &stage, None, &[]).is_empty());
+  }
+
+  #[test]
+  fn test_require_newer_commit() {
+    let policy = DeployPolicy {
+      require_newer_commit: Some(true),
+      ..Default::default()
+    };
+    let deployments = [deployment("stage", "b")];
+    let newer = request(DeploymentType::Stage, "c", "v0.25.3");
+    let same = request(DeploymentType::Stage, "b", "v1.0.0");
+    let older = request(DeploymentType::Stage, "a", "v0.25.3");
+    let unknown = request(DeploymentType::Stage, "x", "v0.25.3");
+    assert!(check(&policy, &newer, None, &deployments).is_empty());
+    assert!(check(&policy, &same, None, &deployments).is_empty());
+    assert!(check(&policy, &older, None, &deployments)[0].contains("older"));
+    assert!(check(&policy, &unknown, None, &deployments).is_empty());
+    // the current deployed commit is older than the branch history
+    assert!(check(&policy, &newer, None, &[deployment("stage", "x")]).is_empty());
+    // no current deployment of this type
+    assert!(check(&policy, &older, None, &[deployment("primary", "c")]).is_empty());
+  }
+
+  #[test]
+  fn test_deny_images() {
+    let policy = DeployPolicy {
+      deny_images: Some(vec!["*-rc*".to_string(), "v0.20.0".to_string()]),
+      ..Default::default()
+    };
+    let rc = request(DeploymentType::Stage, "c", "v1.0.0-rc1");
+    let exact = request(DeploymentType::Stage, "c", "v0.20.0");
+    let stable = request(DeploymentType::Stage, "c", "v1.0.0");
+    assert_eq!(
+      check(&policy, &rc, None, &[]),
+      vec!["The image @subql/node:v1.0.0-rc1 is denied".to_string()]
+    );
+    assert_eq!(check(&policy, &exact, None, &[]).len(), 1);
+    assert!(check(&policy, &stable, None, &[]).is_empty());
+  }
+
+  #[test]
+  fn test_merge() {
+    let project = DeployPolicy {
+      require_newer_commit: Some(false),
+      ..Default::default()
+    };
+    let default = DeployPolicy {
+      require_newer_commit: Some(true),
+      require_stage_before_primary: Some(true),
+      ..Default::default()
+    };
+    let policy = project.merge(default);
+    assert_eq!(policy.require_newer_commit, Some(false));
+    assert_eq!(policy.require_stage_before_primary, Some(true));
+    assert_eq!(policy.deny_images, None);
+  }
+}
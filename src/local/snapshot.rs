@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SubqueryError;
+use crate::subquery::Deployment;
+
+/// Snapshot of a deployment, saved before the deployment is deleted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeploymentSnapshot {
+  pub time: DateTime<Utc>,
+  #[serde(rename = "projectKey")]
+  pub project_key: String,
+  pub deployment: Deployment,
+}
+
+impl DeploymentSnapshot {
+  pub fn new(project_key: impl AsRef<str>, deployment: Deployment) -> Self {
+    Self {
+      time: Utc::now(),
+      project_key: project_key.as_ref().to_string(),
+      deployment,
+    }
+  }
+
+  /// The directory of snapshots
+  pub fn dir() -> color_eyre::Result<PathBuf> {
+    Ok(super::home()?.join("snapshots"))
+  }
+
+  /// Save snapshot to snapshots directory, return the path of snapshot file
+  pub fn save(&self) -> color_eyre::Result<PathBuf> {
+    let dir = Self::dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let name = format!(
+      "{}_{}_{}_{}.json",
+      self.project_key.replace('/', "_"),
+      self.deployment.type_.as_ref(),
+      self.deployment.id,
+      self.time.format("%Y%m%d%H%M%S"),
+    );
+    let path = dir.join(name);
+    std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+    Ok(path)
+  }
+
+  /// Load snapshot from a file path, or a file name in snapshots directory
+  pub fn load(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+    let mut path = path.as_ref().to_path_buf();
+    if !path.exists() {
+      path = Self::dir()?.join(&path);
+    }
+    if !path.exists() {
+      return Err(
+        SubqueryError::Custom(format!("The snapshot {} not found", path.to_string_lossy())).into(),
+      );
+    }
+    let json = std::fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&json)?)
+  }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SubqueryError;
+use crate::subquery::DeploymentMetadata;
+
+/// Declarative spec of a project and its deployments, applied by `subquery apply`. yaml and toml
+/// are supported, the format is chosen by the file extension.
+///
+/// ```yaml
+/// org: fewensa
+/// key: project-key
+/// project:
+///   name: Project
+///   hide: true
+///   repo: https://github.com/fewensa/project-key
+/// stage:
+///   branch: develop
+///   indexer_image_version: ^0.25
+/// primary:
+///   branch: master
+///   commit: "@stage"
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectSpec {
+  /// Org name
+  pub org: String,
+  /// Project key
+  pub key: String,
+  #[serde(default)]
+  pub project: ProjectMetadata,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stage: Option<DeploymentSpec>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub primary: Option<DeploymentSpec>,
+}
+
+/// Project metadata, the field not set will not be changed
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProjectMetadata {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subtitle: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hide: Option<bool>,
+  /// Git repository, only used to create project
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub repo: Option<String>,
+}
+
+/// Deployment settings, the field not set will keep the current value
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeploymentSpec {
+  pub branch: String,
+  /// A commit sha, HEAD~n, or @stage/@primary. default is latest commit of branch
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub commit: Option<String>,
+  /// An exact tag, a semver range or latest-stable
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub indexer_image_version: Option<String>,
+  /// An exact tag, a semver range or latest-stable
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub query_image_version: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub endpoint: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dict_endpoint: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sub_folder: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub batch_size: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subscription: Option<bool>,
+  /// Metadata of the live deployment, written by export for reference and ignored by apply
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metadata: Option<DeploymentMetadata>,
+}
+
+impl ProjectSpec {
+  /// Load spec file, `.toml` is read as toml, otherwise yaml
+  pub fn load(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| {
+      SubqueryError::Custom(format!("Failed to read spec {}: {}", path.display(), e))
+    })?;
+    let spec = if is_toml(path) {
+      toml::from_str(&content).map_err(|e| e.to_string())
+    } else {
+      serde_yaml::from_str(&content).map_err(|e| e.to_string())
+    };
+    spec.map_err(|e| {
+      SubqueryError::Custom(format!("Failed to parse spec {}: {}", path.display(), e)).into()
+    })
+  }
+
+  /// Serialize spec as toml or yaml
+  pub fn to_string(&self, toml: bool) -> color_eyre::Result<String> {
+    if toml {
+      Ok(toml::to_string(self)?)
+    } else {
+      Ok(serde_yaml::to_string(self)?)
+    }
+  }
+
+  /// Save spec file, `.toml` is written as toml, otherwise yaml
+  pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, self.to_string(is_toml(path))?)?;
+    Ok(())
+  }
+
+  /// Project key, e.g. fewensa/project-key
+  pub fn project_key(&self) -> String {
+    format!("{}/{}", self.org, self.key)
+  }
+}
+
+fn is_toml(path: &Path) -> bool {
+  path
+    .extension()
+    .map(|v| v.eq_ignore_ascii_case("toml"))
+    .unwrap_or(false)
+}
@@ -3,13 +3,14 @@ use std::process;
 use color_eyre::Result;
 use structopt::StructOpt;
 
-use crate::command::types::{Opt, SubqueryOpt};
+use crate::command::types::{Opt, Output, SubqueryOpt};
 use crate::error::SubqueryError;
 use crate::subquery::{Config, Subquery};
 
 mod command;
 mod error;
 mod initialize;
+mod local;
 mod subquery;
 
 #[tokio::main]
@@ -35,20 +36,53 @@ async fn main() -> Result<()> {
 }
 
 async fn handle_opt(opt: Opt) -> Result<()> {
-  let token = opt
-    .token
-    .or_else(|| std::env::var("SUBQUERY_TOKEN").ok())
-    .ok_or_else(|| SubqueryError::Custom("Missing access token".to_string()))?;
-  let config = Config::new(token);
-  let subquery = Subquery::new("https://api.subquery.network", config)?;
-
-  let sopt = opt.command;
-  match sopt {
-    SubqueryOpt::User { command } => command::handler::handle_user(&subquery, command).await,
-    SubqueryOpt::Project { command } => command::handler::handle_project(&subquery, command).await,
+  let Opt {
+    token,
+    reason,
+    jq,
+    format,
+    output,
+    command,
+  } = opt;
+  let output = &Output::new(output, jq.as_deref(), format.as_deref())?;
+  // only the commands request the api need access token
+  let subquery = || connect(token, reason);
+  match command {
+    SubqueryOpt::History { command } => command::handler::handle_history(command, output),
+    SubqueryOpt::User { command } => {
+      command::handler::handle_user(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Project { command } => {
+      command::handler::handle_project(&subquery()?, command, output).await
+    }
     SubqueryOpt::Deployment { command } => {
-      command::handler::handle_deployment(&subquery, command).await
+      command::handler::handle_deployment(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Logs { command } => {
+      command::handler::handle_logs(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Images { command } => {
+      command::handler::handle_images(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Repo { command } => {
+      command::handler::handle_repo(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Apply { command } => {
+      command::handler::handle_apply(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Diff { command } => {
+      command::handler::handle_spec_diff(&subquery()?, command, output).await
+    }
+    SubqueryOpt::Export { command } => {
+      command::handler::handle_export(&subquery()?, command, output).await
     }
-    SubqueryOpt::Logs { command } => command::handler::handle_logs(&subquery, command).await,
   }
 }
+
+fn connect(token: Option<String>, reason: Option<String>) -> Result<Subquery> {
+  let token = token
+    .or_else(|| std::env::var("SUBQUERY_TOKEN").ok())
+    .ok_or_else(|| SubqueryError::Custom("Missing access token".to_string()))?;
+  let config = Config::new(token).with_reason(reason);
+  Subquery::new("https://api.subquery.network", config)
+}
@@ -1,11 +1,14 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use reqwest::{Client, Method, RequestBuilder};
 use serde::de::DeserializeOwned;
+use tokio::sync::OnceCell;
 
 use crate::error::SubqueryError;
+use crate::local::{AuditLog, AuditRecord};
 use crate::subquery::{
   Branch, Commit, CreateDeployRequest, CreateProjectResponse, Deployment, Log, Project, SyncStatus,
   User,
@@ -17,6 +20,8 @@ pub struct Subquery {
   client: Client,
   endpoint: String,
   config: Config,
+  /// The user name of access token, only query once for audit log
+  username: Arc<OnceCell<String>>,
 }
 
 impl Subquery {
@@ -28,6 +33,7 @@ impl Subquery {
       client,
       endpoint: endpoint.as_ref().to_string(),
       config,
+      username: Arc::new(OnceCell::new()),
     })
   }
 }
@@ -135,6 +141,70 @@ impl Subquery {
       }
     }
   }
+
+  /// Check the response of api which not return data, only error status is checked
+  fn check(&self, api: impl AsRef<str>, json: impl AsRef<str>) -> color_eyre::Result<()> {
+    let json = json.as_ref();
+    if !json.starts_with('{') {
+      return Ok(());
+    }
+    let value: serde_json::Value = match serde_json::from_str(json) {
+      Ok(v) => v,
+      Err(_) => return Ok(()),
+    };
+    if let Some(sc) = value.get("statusCode") {
+      let code = sc.as_u64().unwrap_or(u64::MAX);
+      if code >= 400 {
+        return Err(
+          SubqueryError::Api(
+            api.as_ref().to_string(),
+            code,
+            value
+              .get("message")
+              .map(|v| v.as_str().unwrap_or("Unknown error").to_string())
+              .unwrap_or_else(|| "No message from server".to_string()),
+          )
+          .into(),
+        );
+      }
+    }
+    Ok(())
+  }
+
+  /// Record the change to audit log, the failure of recording will not break the request.
+  async fn audit<T>(
+    &self,
+    action: impl AsRef<str>,
+    key: impl AsRef<str>,
+    payload: serde_json::Value,
+    result: &color_eyre::Result<T>,
+  ) {
+    let user = self
+      .username
+      .get_or_init(|| async {
+        match self.user().await {
+          Ok(v) => v.username,
+          Err(e) => {
+            tracing::warn!("Failed to query user for audit log: {}", e);
+            "unknown".to_string()
+          }
+        }
+      })
+      .await;
+    let record = AuditRecord {
+      time: chrono::Utc::now(),
+      user: user.clone(),
+      action: action.as_ref().to_string(),
+      project_key: key.as_ref().to_string(),
+      payload,
+      success: result.is_ok(),
+      error: result.as_ref().err().map(|e| e.to_string()),
+      reason: self.config().reason().cloned(),
+    };
+    if let Err(e) = AuditLog::new().and_then(|log| log.append(&record)) {
+      tracing::warn!("Failed to record audit log: {}", e);
+    }
+  }
 }
 
 impl Subquery {
@@ -149,14 +219,22 @@ impl Subquery {
     project: Project,
   ) -> color_eyre::Result<CreateProjectResponse> {
     let api = "/subqueries";
-    let response = self
-      .request(Method::POST, api)?
-      .json(&project)
-      .send()
-      .await?
-      .text()
-      .await?;
-    self.deserialize(api, response)
+    let payload = serde_json::to_value(&project)?;
+    let result = async {
+      let response = self
+        .request(Method::POST, api)?
+        .json(&project)
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.deserialize(api, response)
+    }
+    .await;
+    self
+      .audit("create_project", &project.key, payload, &result)
+      .await;
+    result
   }
 
   pub async fn update_project(&self, project: Project) -> color_eyre::Result<()> {
@@ -173,24 +251,45 @@ impl Subquery {
     if let Some(v) = project.hide {
       data.insert("hide", serde_json::Value::Bool(v));
     }
-    let _response = self
-      .request(Method::PUT, format!("/subqueries/{}", project.key))?
-      .json(&data)
-      .send()
-      .await?
-      .text()
-      .await?;
-    Ok(())
+    let api = format!("/subqueries/{}", project.key);
+    let result = async {
+      let response = self
+        .request(Method::PUT, &api)?
+        .json(&data)
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.check(&api, response)
+    }
+    .await;
+    self
+      .audit(
+        "update_project",
+        &project.key,
+        serde_json::to_value(&data)?,
+        &result,
+      )
+      .await;
+    result
   }
 
   pub async fn delete_project(&self, key: impl AsRef<str>) -> color_eyre::Result<()> {
-    let _response = self
-      .request(Method::DELETE, format!("/subqueries/{}", key.as_ref()))?
-      .send()
-      .await?
-      .text()
-      .await?;
-    Ok(())
+    let api = format!("/subqueries/{}", key.as_ref());
+    let result = async {
+      let response = self
+        .request(Method::DELETE, &api)?
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.check(&api, response)
+    }
+    .await;
+    self
+      .audit("delete_project", key, serde_json::Value::Null, &result)
+      .await;
+    result
   }
 
   pub async fn projects(&self, account: String) -> color_eyre::Result<Vec<Project>> {
@@ -291,14 +390,21 @@ impl Subquery {
       "subFolder": ""
     }
      */
-    let response = self
-      .request(Method::POST, &api)?
-      .json(data)
-      .send()
-      .await?
-      .text()
-      .await?;
-    self.deserialize(api, response)
+    let result = async {
+      let response = self
+        .request(Method::POST, &api)?
+        .json(data)
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.deserialize(&api, response)
+    }
+    .await;
+    self
+      .audit("deploy", key, serde_json::to_value(data)?, &result)
+      .await;
+    result
   }
 
   pub async fn redeploy(
@@ -307,43 +413,55 @@ impl Subquery {
     id: u64,
     data: &CreateDeployRequest,
   ) -> color_eyre::Result<()> {
-    let _response = self
-      .request(
-        Method::PUT,
-        format!("/subqueries/{}/deployments/{}", key.as_ref(), id),
-      )?
-      .json(data)
-      .send()
-      .await?
-      .text()
-      .await?;
-    Ok(())
+    let api = format!("/subqueries/{}/deployments/{}", key.as_ref(), id);
+    let result = async {
+      let response = self
+        .request(Method::PUT, &api)?
+        .json(data)
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.check(&api, response)
+    }
+    .await;
+    let payload = serde_json::json!({ "id": id, "deployment": data });
+    self.audit("redeploy", key, payload, &result).await;
+    result
   }
 
   pub async fn delete_deploy(&self, key: impl AsRef<str>, id: u64) -> color_eyre::Result<()> {
-    let _response = self
-      .request(
-        Method::DELETE,
-        format!("/subqueries/{}/deployments/{}", key.as_ref(), id),
-      )?
-      .send()
-      .await?
-      .text()
-      .await?;
-    Ok(())
+    let api = format!("/subqueries/{}/deployments/{}", key.as_ref(), id);
+    let result = async {
+      let response = self
+        .request(Method::DELETE, &api)?
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.check(&api, response)
+    }
+    .await;
+    let payload = serde_json::json!({ "id": id });
+    self.audit("delete_deploy", key, payload, &result).await;
+    result
   }
 
   pub async fn rebase_deployment(&self, key: impl AsRef<str>, id: u64) -> color_eyre::Result<()> {
-    let _response = self
-      .request(
-        Method::POST,
-        format!("/subqueries/{}/deployments/{}/release", key.as_ref(), id),
-      )?
-      .send()
-      .await?
-      .text()
-      .await?;
-    Ok(())
+    let api = format!("/subqueries/{}/deployments/{}/release", key.as_ref(), id);
+    let result = async {
+      let response = self
+        .request(Method::POST, &api)?
+        .send()
+        .await?
+        .text()
+        .await?;
+      self.check(&api, response)
+    }
+    .await;
+    let payload = serde_json::json!({ "id": id });
+    self.audit("rebase_deployment", key, payload, &result).await;
+    result
   }
 
   pub async fn deployment_sync_status(
@@ -0,0 +1,219 @@
+use crate::error::SubqueryError;
+use crate::subquery::{Commit, Deployment, DeploymentType};
+
+/// Length of a full git commit sha
+const FULL_SHA_LEN: usize = 40;
+
+/// Resolve a commit reference to the full commit sha. the commits is the history of branch, the
+/// newest is first. supported references:
+///
+/// - `HEAD`, `HEAD~3`: the head of branch, or the nth commit before head
+/// - `@stage`, `@primary`: the commit of current stage or primary deployment
+/// - full or abbreviated (at least 4 characters) commit sha in branch history, the history only
+///   has the latest commits, so a full sha not in it is passed through
+///
+/// Tags and branch names are not supported.
+pub fn resolve_commit(
+  commits: &[Commit],
+  deployments: &[Deployment],
+  reference: impl AsRef<str>,
+) -> color_eyre::Result<String> {
+  let reference = reference.as_ref().trim();
+
+  if let Some(type_) = reference.strip_prefix('@') {
+    let type_ = match type_ {
+      "stage" => DeploymentType::Stage,
+      "primary" => DeploymentType::Primary,
+      _ => {
+        return Err(
+          SubqueryError::Custom(format!(
+            "Unknown commit reference {}, use @stage or @primary",
+            reference
+          ))
+          .into(),
+        )
+      }
+    };
+    return deployments
+      .iter()
+      .find(|item| item.type_ == type_)
+      .map(|item| item.version.clone())
+      .ok_or_else(|| {
+        SubqueryError::Custom(format!("Not found any deploy for type: {:?}", type_)).into()
+      });
+  }
+
+  if let Some(offset) = head_offset(reference)? {
+    return commits
+      .get(offset)
+      .map(|item| item.sha.clone())
+      .ok_or_else(|| {
+        SubqueryError::Custom(format!(
+          "The commit reference {} is out of branch history, only {} commits found",
+          reference,
+          commits.len()
+        ))
+        .into()
+      });
+  }
+
+  if reference.len() < 4 || !reference.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Err(
+      SubqueryError::Custom(format!(
+        "Unknown commit reference {}, tags and branch names are not supported, use a commit sha (at least 4 characters), HEAD~n, @stage or @primary",
+        reference
+      ))
+      .into(),
+    );
+  }
+  let reference = reference.to_lowercase();
+  if is_full_sha(&reference) {
+    return Ok(reference);
+  }
+  let matched = commits
+    .iter()
+    .filter(|item| item.sha.to_lowercase().starts_with(&reference))
+    .collect::<Vec<&Commit>>();
+  match matched.len() {
+    0 => Err(
+      SubqueryError::Custom(format!(
+        "The commit {} not found in branch history",
+        reference
+      ))
+      .into(),
+    ),
+    1 => Ok(matched[0].sha.clone()),
+    _ => Err(
+      SubqueryError::Custom(format!(
+        "The commit {} is ambiguous, candidates: {}",
+        reference,
+        matched
+          .iter()
+          .map(|item| item.sha.as_str())
+          .collect::<Vec<&str>>()
+          .join(", ")
+      ))
+      .into(),
+    ),
+  }
+}
+
+/// Whether the reference is a full commit sha, it needs no branch history to resolve
+pub fn is_full_sha(reference: &str) -> bool {
+  reference.len() == FULL_SHA_LEN && reference.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// HEAD -> 0, HEAD~3 -> 3, not a head reference -> None
+fn head_offset(reference: &str) -> color_eyre::Result<Option<usize>> {
+  let rest = match reference.strip_prefix("HEAD") {
+    Some(v) => v,
+    None => return Ok(None),
+  };
+  if rest.is_empty() {
+    return Ok(Some(0));
+  }
+  let offset = rest
+    .strip_prefix('~')
+    .and_then(|v| {
+      if v.is_empty() {
+        Some(1)
+      } else {
+        v.parse().ok()
+      }
+    })
+    .ok_or_else(|| SubqueryError::Custom(format!("Wrong commit reference {}", reference)))?;
+  Ok(Some(offset))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::subquery::fixtures;
+
+  fn commits() -> Vec<Commit> {
+    fixtures::commits(&["abcd1234ef", "abce5678aa", "1234aaaabb", "9876ccccdd"])
+  }
+
+  fn deployments() -> Vec<Deployment> {
+    vec![fixtures::deployment("stage", "1234aaaabb")]
+  }
+
+  fn resolve(reference: &str) -> color_eyre::Result<String> {
+    resolve_commit(&commits(), &deployments(), reference)
+  }
+
+  #[test]
+  fn test_full_and_short_sha() {
+    assert_eq!(resolve("9876ccccdd").unwrap(), "9876ccccdd");
+    assert_eq!(resolve("9876").unwrap(), "9876ccccdd");
+    assert_eq!(resolve("ABCD1").unwrap(), "abcd1234ef");
+  }
+
+  #[test]
+  fn test_unknown_sha() {
+    let err = resolve("abc").unwrap_err().to_string();
+    assert!(err.contains("Unknown commit reference"), "{}", err);
+    let err = resolve("main").unwrap_err().to_string();
+    assert!(err.contains("Unknown commit reference"), "{}", err);
+    let err = resolve("abc0").unwrap_err().to_string();
+    assert!(err.contains("not found in branch history"), "{}", err);
+  }
+
+  #[test]
+  fn test_full_sha_passed_through() {
+    let sha = "0123456789abcdef0123456789abcdef01234567";
+    assert!(is_full_sha(sha));
+    assert!(!is_full_sha("0123456789"));
+    assert_eq!(resolve(sha).unwrap(), sha);
+    assert_eq!(resolve(&sha.to_uppercase()).unwrap(), sha);
+  }
+
+  #[test]
+  fn test_tag_rejected() {
+    let err = resolve("v1.0.0").unwrap_err().to_string();
+    assert!(
+      err.contains("tags and branch names are not supported"),
+      "{}",
+      err
+    );
+  }
+
+  #[test]
+  fn test_ambiguous_prefix() {
+    let mut commits = commits();
+    commits[1].sha = "abcd5678aa".to_string();
+    let err = resolve_commit(&commits, &[], "abcd")
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("ambiguous"), "{}", err);
+    assert!(err.contains("abcd1234ef, abcd5678aa"), "{}", err);
+  }
+
+  #[test]
+  fn test_head() {
+    assert_eq!(resolve("HEAD").unwrap(), "abcd1234ef");
+    assert_eq!(resolve("HEAD~").unwrap(), "abce5678aa");
+    assert_eq!(resolve("HEAD~3").unwrap(), "9876ccccdd");
+    let err = resolve("HEAD~4").unwrap_err().to_string();
+    assert!(err.contains("out of branch history"), "{}", err);
+    assert!(resolve("HEAD~x").is_err());
+    assert!(resolve("HEAD^").is_err());
+  }
+
+  #[test]
+  fn test_deployed_commit() {
+    assert_eq!(resolve("@stage").unwrap(), "1234aaaabb");
+    let err = resolve("@primary").unwrap_err().to_string();
+    assert!(err.contains("Not found any deploy"), "{}", err);
+    let err = resolve("@dev").unwrap_err().to_string();
+    assert!(err.contains("Unknown commit reference"), "{}", err);
+  }
+
+  #[test]
+  fn test_head_offset() {
+    assert_eq!(head_offset("HEAD").unwrap(), Some(0));
+    assert_eq!(head_offset("HEAD~2").unwrap(), Some(2));
+    assert_eq!(head_offset("abcd").unwrap(), None);
+    assert!(head_offset("HEAD~-1").is_err());
+  }
+}
@@ -2,12 +2,22 @@
 #[derive(Clone, Debug)]
 pub struct Config {
   token: String,
+  reason: Option<String>,
 }
 
 impl Config {
   /// Create new config instance
   pub fn new(token: String) -> Self {
-    Self { token }
+    Self {
+      token,
+      reason: None,
+    }
+  }
+
+  /// Set the reason of changes, it will be recorded to audit log
+  pub fn with_reason(mut self, reason: Option<String>) -> Self {
+    self.reason = reason;
+    self
   }
 }
 
@@ -16,4 +26,9 @@ impl Config {
   pub fn token(&self) -> &String {
     &self.token
   }
+
+  /// Get reason of changes
+  pub fn reason(&self) -> Option<&String> {
+    self.reason.as_ref()
+  }
 }
@@ -0,0 +1,192 @@
+use semver::{Version, VersionReq};
+
+use crate::error::SubqueryError;
+
+/// Select the latest stable version, pre-release tags are excluded.
+pub const LATEST_STABLE: &str = "latest-stable";
+
+/// Parse image tag to semver version, the tag looks like `v0.25.3`
+pub fn parse_image_tag(tag: impl AsRef<str>) -> Option<Version> {
+  let tag = tag.as_ref();
+  Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Stable tags sorted by version, the newest is first
+pub fn stable_image_tags(tags: &[String]) -> Vec<(Version, &String)> {
+  let mut versions = tags
+    .iter()
+    .filter_map(|tag| parse_image_tag(tag).map(|version| (version, tag)))
+    .filter(|(version, _)| version.pre.is_empty())
+    .collect::<Vec<(Version, &String)>>();
+  versions.sort_by(|a, b| b.0.cmp(&a.0));
+  versions
+}
+
+/// How many stable versions are newer than the tag, none if the tag is not a semver version
+pub fn image_versions_behind(tags: &[String], tag: impl AsRef<str>) -> Option<usize> {
+  let current = parse_image_tag(tag)?;
+  Some(
+    stable_image_tags(tags)
+      .iter()
+      .filter(|(version, _)| version > &current)
+      .count(),
+  )
+}
+
+/// Whether the version is a constraint which should be resolved from image tags,
+/// e.g. `latest-stable`, `^0.25`, `~1.2`, `>=0.8, <0.9`, `0.25.*`
+pub fn is_image_version_constraint(version: impl AsRef<str>) -> bool {
+  let version = version.as_ref().trim();
+  version == LATEST_STABLE
+    || version.starts_with(['^', '~', '>', '<', '=', '*'])
+    || version.contains('*')
+    || version.split('.').any(|v| v == "x" || v == "X")
+}
+
+/// Resolve image version from image tags. no constraint or `latest-stable` will choose the newest
+/// stable tag, a semver range will choose the newest stable tag matched, otherwise the version
+/// is an exact tag and return it directly.
+pub fn resolve_image_version(
+  name: impl AsRef<str>,
+  tags: &[String],
+  constraint: Option<&str>,
+) -> color_eyre::Result<String> {
+  let name = name.as_ref();
+  let constraint = constraint.map(|v| v.trim()).unwrap_or(LATEST_STABLE);
+  if !is_image_version_constraint(constraint) {
+    return Ok(constraint.to_string());
+  }
+  let stable_tags = stable_image_tags(tags);
+  if constraint == LATEST_STABLE {
+    return stable_tags
+      .first()
+      .map(|(_, tag)| tag.to_string())
+      .ok_or_else(|| {
+        SubqueryError::Custom(format!("No stable image tag found for {}", name)).into()
+      });
+  }
+  let req = VersionReq::parse(constraint).map_err(|e| {
+    SubqueryError::Custom(format!(
+      "Wrong image version constraint {} for {}: {}",
+      constraint, name, e
+    ))
+  })?;
+  stable_tags
+    .iter()
+    .find(|(version, _)| req.matches(version))
+    .map(|(_, tag)| tag.to_string())
+    .ok_or_else(|| {
+      SubqueryError::Custom(format!(
+        "No stable image tag of {} matches {}",
+        name, constraint
+      ))
+      .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tags() -> Vec<String> {
+    [
+      "v0.24.0",
+      "v0.25.0",
+      "v0.25.3",
+      "v0.26.0-rc1",
+      "v1.2.0",
+      "v1.2.5",
+      "v1.3.0-rc.2",
+      "latest",
+    ]
+    .iter()
+    .map(|v| v.to_string())
+    .collect()
+  }
+
+  #[test]
+  fn test_constraint() {
+    assert!(is_image_version_constraint("latest-stable"));
+    assert!(is_image_version_constraint("^0.25"));
+    assert!(is_image_version_constraint("~1.2"));
+    assert!(is_image_version_constraint(">=0.8, <0.9"));
+    assert!(is_image_version_constraint("0.25.*"));
+    assert!(is_image_version_constraint("1.x"));
+    assert!(!is_image_version_constraint("v0.25.3"));
+    assert!(!is_image_version_constraint("v1.3.0-rc.2"));
+    assert!(!is_image_version_constraint("latest"));
+  }
+
+  #[test]
+  fn test_stable_tags_exclude_pre_release() {
+    let tags = tags();
+    let stable = stable_image_tags(&tags)
+      .into_iter()
+      .map(|(_, tag)| tag.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      stable,
+      vec!["v1.2.5", "v1.2.0", "v0.25.3", "v0.25.0", "v0.24.0"]
+    );
+  }
+
+  #[test]
+  fn test_resolve_latest_stable() {
+    let tags = tags();
+    assert_eq!(
+      resolve_image_version("node", &tags, None).unwrap(),
+      "v1.2.5"
+    );
+    assert_eq!(
+      resolve_image_version("node", &tags, Some("latest-stable")).unwrap(),
+      "v1.2.5"
+    );
+  }
+
+  #[test]
+  fn test_resolve_range_skips_rc() {
+    let tags = tags();
+    assert_eq!(
+      resolve_image_version("node", &tags, Some("^0.25")).unwrap(),
+      "v0.25.3"
+    );
+    assert_eq!(
+      resolve_image_version("node", &tags, Some("~1.2")).unwrap(),
+      "v1.2.5"
+    );
+    assert_eq!(
+      resolve_image_version("node", &tags, Some(">=1.3"))
+        .unwrap_err()
+        .to_string(),
+      SubqueryError::Custom("No stable image tag of node matches >=1.3".to_string()).to_string()
+    );
+  }
+
+  #[test]
+  fn test_resolve_exact_tag() {
+    let tags = tags();
+    assert_eq!(
+      resolve_image_version("node", &tags, Some("v1.3.0-rc.2")).unwrap(),
+      "v1.3.0-rc.2"
+    );
+    // exact tags are passed through even if not listed
+    assert_eq!(
+      resolve_image_version("node", &tags, Some("v9.9.9")).unwrap(),
+      "v9.9.9"
+    );
+  }
+
+  #[test]
+  fn test_resolve_errors() {
+    assert!(resolve_image_version("node", &[], None).is_err());
+    assert!(resolve_image_version("node", &tags(), Some("^abc")).is_err());
+  }
+
+  #[test]
+  fn test_versions_behind() {
+    let tags = tags();
+    assert_eq!(image_versions_behind(&tags, "v0.25.3"), Some(2));
+    assert_eq!(image_versions_behind(&tags, "v1.2.5"), Some(0));
+    assert_eq!(image_versions_behind(&tags, "latest"), None);
+  }
+}
@@ -2,6 +2,9 @@ pub use self::api::*;
 pub use self::config::*;
 pub use self::types::*;
 
+pub mod commit;
+pub mod image;
+
 mod api;
 mod config;
 mod patch;
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
-use strum::{EnumString, EnumVariantNames};
+use strum::{AsRefStr, EnumString, EnumVariantNames};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct User {
@@ -110,6 +110,45 @@ pub struct Deployment {
   #[serde(rename = "queryClusterUrl")]
   pub query_cluster_url: Option<String>,
   pub metadata: Option<DeploymentMetadata>,
+  #[serde(rename = "advancedSettings")]
+  pub advanced_settings: Option<AdvancedSettings>,
+}
+
+impl Deployment {
+  /// Indexer image version, the tag of indexer image
+  pub fn indexer_image_version(&self) -> Option<&str> {
+    image_tag(&self.indexer_image)
+  }
+
+  /// Query image version, the tag of query image
+  pub fn query_image_version(&self) -> Option<&str> {
+    image_tag(&self.query_image)
+  }
+
+  /// Indexer batch size of this deployment
+  pub fn batch_size(&self) -> Option<u32> {
+    self
+      .advanced_settings
+      .as_ref()
+      .map(|v| v.subql_node.batch_size)
+      .or_else(|| self.metadata.as_ref().map(|v| v.indexer_batch_size))
+  }
+
+  /// Subscription activation of this deployment
+  pub fn subscription(&self) -> Option<bool> {
+    self
+      .advanced_settings
+      .as_ref()
+      .map(|v| v.subql_node.subscription)
+  }
+}
+
+/// onfinality/subql-node:v0.25.3 -> v0.25.3
+fn image_tag(image: &str) -> Option<&str> {
+  image
+    .rsplit_once(':')
+    .map(|(_, tag)| tag)
+    .filter(|tag| !tag.is_empty())
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -138,7 +177,28 @@ pub struct CreateDeployRequest {
   pub advanced_settings: AdvancedSettings,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, EnumVariantNames)]
+impl CreateDeployRequest {
+  /// Create a request which deploys the same commit, images and settings as the deployment
+  pub fn from_deployment(deployment: &Deployment) -> Self {
+    Self {
+      commit: Some(deployment.version.clone()),
+      endpoint: deployment.endpoint.clone(),
+      dict_endpoint: deployment.dict_endpoint.clone(),
+      indexer_image_version: deployment.indexer_image_version().map(|v| v.to_string()),
+      query_image_version: deployment.query_image_version().map(|v| v.to_string()),
+      type_: deployment.type_.clone(),
+      sub_folder: deployment.sub_folder.clone(),
+      advanced_settings: AdvancedSettings::new(
+        deployment.batch_size().unwrap_or(DEFAULT_BATCH_SIZE),
+        deployment.subscription().unwrap_or(false),
+      ),
+    }
+  }
+}
+
+#[derive(
+  Clone, Debug, Eq, PartialEq, Serialize, Deserialize, AsRefStr, EnumString, EnumVariantNames,
+)]
 #[strum(serialize_all = "kebab_case")]
 pub enum DeploymentType {
   #[serde(rename = "primary")]
@@ -222,6 +282,17 @@ pub struct SyncStatus {
   pub target_block: u32,
 }
 
+impl SyncStatus {
+  /// Sync progress in percent with two decimals, 0 if the target block is not known yet
+  pub fn percent(&self) -> f64 {
+    if self.target_block == 0 {
+      return 0f64;
+    }
+    let percent = self.processing_block as f64 / self.target_block as f64 * 100f64;
+    (percent * 100f64).round() / 100f64
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Log {
   #[serde(rename = "startTime")]
@@ -253,6 +324,9 @@ pub struct SubQuerySettings {
   pub subscription: bool,
 }
 
+/// Indexer batch size if not set
+pub const DEFAULT_BATCH_SIZE: u32 = 30;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AdvancedSettings {
   #[serde(rename = "@subql/node")]
@@ -272,3 +346,112 @@ impl AdvancedSettings {
     }
   }
 }
+
+/// Api values for tests, built from json as the api responds
+#[cfg(test)]
+pub mod fixtures {
+  use super::*;
+
+  pub fn deployment(type_: &str, version: &str) -> Deployment {
+    serde_json::from_value(serde_json::json!({
+      "createdAt": "2021-12-16T05:19:15Z",
+      "updatedAt": null,
+      "id": 8528,
+      "projectKey": "fewensa/project",
+      "version": version,
+      "status": "running",
+      "cluster": "jm",
+      "indexerImage": "onfinality/subql-node:v0.25.3",
+      "queryImage": "onfinality/subql-query:v0.8.0",
+      "subFolder": "",
+      "endpoint": "",
+      "dictEndpoint": "",
+      "type": type_,
+      "queryUrl": "https://api.subquery.network/sq/fewensa/project",
+      "queryClusterUrl": null,
+      "metadata": null,
+      "advancedSettings": {
+        "@subql/node": { "batchSize": 30, "subscription": false },
+        "@subql/query": { "subscription": false },
+      },
+    }))
+    .unwrap()
+  }
+
+  pub fn project(key: &str) -> Project {
+    serde_json::from_value(serde_json::json!({
+      "apiVersion": "v1",
+      "createdAt": "2021-12-16T05:19:15Z",
+      "updatedAt": null,
+      "key": key,
+      "account": "fewensa",
+      "name": "Project",
+      "network": "polkadot",
+      "deployed": true,
+      "logoUrl": "",
+      "subtitle": "",
+      "description": "The project",
+      "gitRepository": "https://github.com/fewensa/project",
+      "hide": false,
+      "dedicateDBKey": null,
+      "queryUrl": "",
+      "deployment": null,
+    }))
+    .unwrap()
+  }
+
+  /// The branch history, the newest is first
+  pub fn commits(shas: &[&str]) -> Vec<Commit> {
+    shas
+      .iter()
+      .map(|sha| {
+        serde_json::from_value(serde_json::json!({
+          "sha": sha,
+          "message": "commit",
+          "time": "2021-12-16T05:19:15Z",
+          "author": { "name": "fewensa", "avatarUrl": null },
+        }))
+        .unwrap()
+      })
+      .collect()
+  }
+
+  pub fn branch(name: &str, protected: bool, head: &str) -> Branch {
+    serde_json::from_value(serde_json::json!({
+      "name": name,
+      "protected": protected,
+      "commit": { "sha": head, "url": "" },
+    }))
+    .unwrap()
+  }
+
+  pub fn deploy_request(type_: DeploymentType, commit: &str, indexer: &str) -> CreateDeployRequest {
+    CreateDeployRequest {
+      commit: Some(commit.to_string()),
+      endpoint: None,
+      dict_endpoint: None,
+      indexer_image_version: Some(indexer.to_string()),
+      query_image_version: Some("v0.8.0".to_string()),
+      type_,
+      sub_folder: None,
+      advanced_settings: AdvancedSettings::new(DEFAULT_BATCH_SIZE, false),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sync_percent() {
+    let status = |processing_block, target_block| SyncStatus {
+      processing_block,
+      target_block,
+    };
+    assert_eq!(status(1234, 10000).percent(), 12.34);
+    assert_eq!(status(1, 3).percent(), 33.33);
+    assert_eq!(status(10, 10).percent(), 100f64);
+    assert_eq!(status(0, 0).percent(), 0f64);
+  }
+}